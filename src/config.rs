@@ -15,16 +15,18 @@
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
 
 use app_dirs::{AppDataType, AppInfo};
 use log::info;
 use log::trace;
-use mut_static::MutStatic;
+use log::warn;
 use parity_codec::Decode;
 use runtime_primitives::generic::DigestItem;
 use serde_derive::{Deserialize, Serialize};
@@ -34,16 +36,30 @@ use yee_primitives::Hrp;
 use yee_runtime::{AuthorityId, Hash};
 use yee_sharding::ShardingDigestItem;
 
-use lazy_static::lazy_static;
-
 use crate::errors;
 use crate::opt::Opt;
-use crate::rpc::client::RpcClient;
+use crate::rpc::client::{self, RpcClient};
+use crate::watch;
 
-lazy_static! {
-	pub static ref HRP: MutStatic<Hrp> = MutStatic::new();
-	pub static ref SHARD_COUNT: MutStatic<u16> = MutStatic::new();
-}
+use self::overrides::{Override, ENV_PREFIX};
+
+mod overrides;
+mod validation;
+
+/// Name of the network of the top level shards
+pub const DEFAULT_NETWORK: &str = "default";
+
+/// Addresses of a batch request, if not configured
+const DEFAULT_MAX_ADDRESSES: usize = 100;
+
+const SHARD_COUNT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Version byte of unsigned extrinsics, as the sharding inherent
+const UNSIGNED_VERSION: u8 = 1;
+
+/// Module of the sharding inherent and its `set_shard_info` method
+const SHARDING_MODULE: u8 = 5;
+const SET_SHARD_INFO: u8 = 0;
 
 /// Config
 /// # Configure file description
@@ -64,17 +80,297 @@ lazy_static! {
 ///
 /// [shards.3]
 /// rpc = ["http://127.0.0.1:9333"]
+///
+/// # Optional, more networks served at /<name> or with ?network=<name>
+/// [networks.testnet.shards.0]
+/// rpc = ["http://127.0.0.1:19033"]
+///
+/// [networks.testnet.shards.1]
+/// rpc = ["http://127.0.0.1:19133"]
+///
+/// # Optional, no validation if absent
+/// [server]
+/// cors = ["https://wallet.yeefoundation.com"]
+/// hosts = ["rpc.yeefoundation.com"]
+/// ws_origins = ["https://wallet.yeefoundation.com"]
+/// trusted_proxies = ["10.0.0.1"]
+///
+/// # Optional, serve https:// and wss:// if present
+/// [tls]
+/// cert = "/etc/yee-panel/cert.pem"
+/// key = "/etc/yee-panel/key.pem"
+///
+/// # Optional, disabled if absent
+/// [access_log]
+/// enabled = true
+/// format = "json"
+///
+/// # Optional, authentication is disabled if absent
+/// [auth]
+/// public_methods = ["chain_*", "state_*"]
+///
+/// [[auth.keys]]
+/// identity = "partner-a"
+/// token = "8c3f0c9e4b6d4f0a"
+/// methods = ["*"]
 /// ```
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Shard {
 	pub rpc: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Config {
+	#[serde(default)]
+	pub shards: HashMap<String, Shard>,
+	#[serde(default)]
+	pub networks: HashMap<String, Network>,
+	#[serde(default)]
+	pub server: Server,
+	pub tls: Option<Tls>,
+	pub auth: Option<Auth>,
+	#[serde(default)]
+	pub access_log: AccessLog,
+	#[serde(default)]
+	pub format: Format,
+	#[serde(default)]
+	pub submit: Submit,
+	#[serde(default)]
+	pub batch: Batch,
+}
+
+/// Network
+///
+/// The shards of a network other than the default one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Network {
 	pub shards: HashMap<String, Shard>,
 }
 
+impl Config {
+	/// Shards of the network, the top level shards are the default network
+	pub fn shards(&self, network: &str) -> Option<&HashMap<String, Shard>> {
+		if network == DEFAULT_NETWORK {
+			Some(&self.shards).filter(|x| !x.is_empty())
+		} else {
+			self.networks.get(network).map(|x| &x.shards)
+		}
+	}
+
+	pub fn network_names(&self) -> Vec<String> {
+		let mut names = vec![];
+		if !self.shards.is_empty() {
+			names.push(DEFAULT_NETWORK.to_string());
+		}
+		let mut networks = self.networks.keys().cloned().collect::<Vec<_>>();
+		networks.sort();
+		names.extend(networks);
+		names
+	}
+}
+
+/// Server
+///
+/// `None` disables the validation, the listening address is always allowed in `hosts`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Server {
+	/// Origins allowed by HTTP CORS
+	pub cors: Option<Vec<String>>,
+	/// Values allowed in the Host header of HTTP and WS requests
+	pub hosts: Option<Vec<String>>,
+	/// Origins allowed to open WS connections
+	pub ws_origins: Option<Vec<String>>,
+	/// IP addresses of the reverse proxies in front of the panel, `X-Forwarded-For` and
+	/// `X-Real-IP` are ignored if absent
+	pub trusted_proxies: Option<Vec<String>>,
+}
+
+/// TLS
+///
+/// PEM encoded certificate chain and PKCS8 or RSA private key,
+/// reloaded when the files change.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tls {
+	pub cert: String,
+	pub key: String,
+}
+
+/// Authentication
+///
+/// Methods matching `public_methods` can be called without a key,
+/// others need a key whose `methods` match.
+/// A pattern is a method name, a prefix ending with `*`, or `*` for all methods.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Auth {
+	#[serde(default)]
+	pub public_methods: Vec<String>,
+	#[serde(default)]
+	pub keys: Vec<AuthKey>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthKey {
+	pub identity: String,
+	pub token: String,
+	pub methods: Vec<String>,
+}
+
+/// Access log
+///
+/// `text` lines are written to the logger with target `access`,
+/// `json` lines are written to stdout.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AccessLog {
+	#[serde(default)]
+	pub enabled: bool,
+	#[serde(default)]
+	pub format: AccessLogFormat,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+	Text,
+	Json,
+}
+
+impl Default for AccessLogFormat {
+	fn default() -> Self {
+		AccessLogFormat::Text
+	}
+}
+
+/// Checks of the extrinsics before they are submitted
+///
+/// If `validate` is set, an extrinsic with an invalid signature, an expired era, a stale nonce
+/// or a transfer value above the free balance of the sender is rejected instead of submitted.
+/// Fees are not checked, the value alone is compared with the free balance.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Submit {
+	#[serde(default)]
+	pub validate: bool,
+}
+
+/// Batch requests
+///
+/// `state_getBalances` and `state_getNonces` reject more than `max_addresses` addresses.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Batch {
+	#[serde(default = "default_max_addresses")]
+	pub max_addresses: usize,
+}
+
+impl Default for Batch {
+	fn default() -> Self {
+		Batch {
+			max_addresses: default_max_addresses(),
+		}
+	}
+}
+
+fn default_max_addresses() -> usize {
+	DEFAULT_MAX_ADDRESSES
+}
+
+/// Format of the responses
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct Format {
+	#[serde(default)]
+	pub balance: BalanceFormat,
+	/// Decimals of the token, formatted amounts are added to the responses if set
+	pub decimals: Option<u8>,
+}
+
+/// Representation of balances and transfer values
+///
+/// Balances are u128 and may exceed the integers JavaScript clients can represent exactly,
+/// `string` and `hex` are exact for any client.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceFormat {
+	/// Decimal string
+	String,
+	Hex,
+	Number,
+}
+
+impl Default for BalanceFormat {
+	fn default() -> Self {
+		BalanceFormat::Number
+	}
+}
+
+/// Config shared by the handlers
+///
+/// The config is swapped as a whole on reload, readers keep the snapshot they got.
+#[derive(Clone, Default)]
+pub struct SharedConfig(Arc<RwLock<Arc<Config>>>);
+
+impl SharedConfig {
+	pub fn new(config: Config) -> Self {
+		SharedConfig(Arc::new(RwLock::new(Arc::new(config))))
+	}
+
+	pub fn get(&self) -> Arc<Config> {
+		self.0.read().expect("qed").clone()
+	}
+
+	pub fn set(&self, config: Config) {
+		*self.0.write().expect("qed") = Arc::new(config);
+	}
+}
+
+/// Info of a network discovered from its nodes
+#[derive(Clone, Debug)]
+pub struct NetworkInfo {
+	pub hrp: Hrp,
+	pub shard_count: u16,
+}
+
+/// Shard info set by the sharding inherent of each block
+#[derive(Decode, Debug, Clone, PartialEq)]
+pub struct ShardInfo {
+	pub num: u16,
+	pub count: u16,
+	/// Present while the chain is scaling out
+	pub scale_out: Option<ScaleOut>,
+}
+
+#[derive(Decode, Debug, Clone, PartialEq)]
+pub struct ScaleOut {
+	pub shard_num: u16,
+}
+
+impl ShardInfo {
+	/// Shard count once the scale out in progress is done, each shard splits in two
+	pub fn target_count(&self) -> u16 {
+		match self.scale_out {
+			Some(_) => self.count.saturating_mul(2),
+			None => self.count,
+		}
+	}
+}
+
+/// Info of the networks by name
+#[derive(Clone, Default)]
+pub struct Networks(Arc<RwLock<HashMap<String, NetworkInfo>>>);
+
+impl Networks {
+	pub fn get(&self, network: &str) -> Option<NetworkInfo> {
+		self.0.read().expect("qed").get(network).cloned()
+	}
+
+	fn insert(&self, network: String, info: NetworkInfo) {
+		self.0.write().expect("qed").insert(network, info);
+	}
+
+	fn set_shard_count(&self, network: &str, shard_count: u16) {
+		if let Some(info) = self.0.write().expect("qed").get_mut(network) {
+			info.shard_count = shard_count;
+		}
+	}
+}
+
 pub struct VersionInfo {
 	pub version: &'static str,
 	pub executable_name: &'static str,
@@ -82,40 +378,264 @@ pub struct VersionInfo {
 }
 
 pub fn get_config(opt: &Opt, version: &VersionInfo) -> errors::Result<Config> {
-	let conf_path = conf_path(&base_path(opt, version));
-
-	let conf_path = conf_path.join("config.toml");
+	let conf_path = conf_file(opt, version);
 
 	trace!("conf_path:{}", conf_path.to_string_lossy());
 
-	let mut file =
-		File::open(&conf_path).map_err(|_e| format!("Non-existed conf file: {:?}", conf_path))?;
+	if !conf_path.exists() {
+		info!(
+			"No conf file at {:?}, configure with {}* environment variables and command line options",
+			conf_path, ENV_PREFIX
+		);
+	}
 
-	let mut str_val = String::new();
-	file.read_to_string(&mut str_val)?;
+	let conf = read_config(&conf_path, &overrides::get_overrides(opt))?;
+
+	Ok(conf)
+}
+
+/// Discover the hrp and the shard count of the configured networks
+pub fn init_networks(config: &Config) -> errors::Result<Networks> {
+	let networks = Networks::default();
+
+	let mut runtime = Runtime::new().expect("Failed to start new Runtime");
+	for network in config.network_names() {
+		let info = init_network(&mut runtime, config, &network)?;
+		networks.insert(network, info);
+	}
+
+	Ok(networks)
+}
+
+/// Keep tracking the shard count of the networks from the sharding inherent of the best blocks
+///
+/// A scale out is announced by the inherent before the shard count changes, the missing
+/// shards of the new count are warned then. Addresses are routed with the new shard count
+/// as soon as the inherent carries it.
+pub fn track_shard_count(config: SharedConfig, networks: Networks) {
+	let result = thread::Builder::new()
+		.name("shard-count".to_string())
+		.spawn(move || {
+			let mut runtime = Runtime::new().expect("Failed to start new Runtime");
+			let mut scaling_out = HashMap::new();
+			loop {
+				thread::sleep(SHARD_COUNT_INTERVAL);
+				let config = config.get();
+				for network in config.network_names() {
+					let current = match networks.get(&network) {
+						Some(info) => info.shard_count,
+						None => continue,
+					};
+					let shard_info = match get_shard_info(&mut runtime, &config, &network) {
+						Ok(v) => v,
+						Err(e) => {
+							warn!("Failed to get shard_count of {}: {}", network, e);
+							continue;
+						}
+					};
+					let target_count = shard_info.target_count();
+					if shard_info.scale_out.is_some()
+						&& scaling_out.insert(network.clone(), target_count) != Some(target_count)
+					{
+						warn!(
+							"Scale out of {} started, shard_count: {} -> {}",
+							network, shard_info.count, target_count
+						);
+						if let Err(e) = check_shard_count(&config, &network, target_count) {
+							warn!("{}", e);
+						}
+					}
+					if shard_info.scale_out.is_none() {
+						scaling_out.remove(&network);
+					}
+					let shard_count = shard_info.count;
+					if shard_count == current {
+						continue;
+					}
+					if shard_count > current {
+						warn!(
+							"Scale out of {} done, shard_count: {} -> {}",
+							network, current, shard_count
+						);
+					} else {
+						warn!(
+							"Shard count of {} changed, shard_count: {} -> {}",
+							network, current, shard_count
+						);
+					}
+					networks.set_shard_count(&network, shard_count);
+					if let Err(e) = check_shard_count(&config, &network, shard_count) {
+						warn!("{}", e);
+					}
+				}
+			}
+		});
+
+	if let Err(e) = result {
+		warn!("Failed to track shard_count: {:?}", e);
+	}
+}
+
+/// Reload the config when the conf file changes or on SIGHUP
+///
+/// Shards, auth, access log and `trusted_proxies` take effect for the next requests, open WS
+/// sessions are kept. Networks added are discovered in the background and served once
+/// discovered. Changes of `cors`, `hosts` and `ws_origins` of `[server]` and of `[tls]` need
+/// a restart.
+pub fn watch_config(opt: &Opt, version: &VersionInfo, config: SharedConfig, networks: Networks) {
+	let conf_path = conf_file(opt, version);
+	let overrides = overrides::get_overrides(opt);
+	let discovering = Arc::new(Mutex::new(HashSet::new()));
+
+	let reload = move || reload_config(&conf_path, &overrides, &config, &networks, &discovering);
+
+	watch::watch_files("config", vec![conf_path.clone()], reload.clone());
+
+	let result = signal_hook::iterator::Signals::new(&[signal_hook::SIGHUP]).and_then(|signals| {
+		thread::Builder::new()
+			.name("signal-hup".to_string())
+			.spawn(move || {
+				for _ in signals.forever() {
+					info!("SIGHUP received");
+					reload();
+				}
+			})
+	});
+	if let Err(e) = result {
+		warn!("Failed to handle SIGHUP: {:?}", e);
+	}
+}
+
+fn reload_config(
+	conf_path: &Path,
+	overrides: &[Override],
+	config: &SharedConfig,
+	networks: &Networks,
+	discovering: &Arc<Mutex<HashSet<String>>>,
+) {
+	let new_config = read_config(conf_path, overrides).and_then(|new_config| {
+		for network in new_config.network_names() {
+			if let Some(info) = networks.get(&network) {
+				check_shard_count(&new_config, &network, info.shard_count)?;
+			}
+		}
+		Ok(new_config)
+	});
+	let new_config = match new_config {
+		Ok(v) => v,
+		Err(e) => {
+			warn!("Failed to reload conf file, keep the current config: {}", e);
+			return;
+		}
+	};
+
+	let current = config.get();
+	if !restart_free(&current.server, &new_config.server) || current.tls != new_config.tls {
+		warn!("Changes of cors, hosts and ws_origins of [server] and of [tls] take effect after restart");
+	}
+
+	let added = new_config
+		.network_names()
+		.into_iter()
+		.filter(|x| networks.get(x).is_none())
+		.filter(|x| discovering.lock().expect("qed").insert(x.clone()))
+		.collect::<Vec<_>>();
+	if !added.is_empty() {
+		discover_networks(
+			new_config.clone(),
+			added,
+			networks.clone(),
+			discovering.clone(),
+		);
+	}
+
+	config.set(new_config);
+
+	info!("Reloaded conf file: {:?}", conf_path);
+}
 
-	let conf: Config = toml::from_str(&str_val).map_err(|_e| "Error reading conf file")?;
+/// Whether the changes of `[server]` apply without restart, only `trusted_proxies` is read
+/// for each request
+fn restart_free(current: &Server, new: &Server) -> bool {
+	Server {
+		trusted_proxies: new.trusted_proxies.clone(),
+		..current.clone()
+	} == *new
+}
 
-	init_global(&conf)?;
+/// Discover the networks added by a reload off the reload thread, so unreachable nodes do not
+/// hold the next reloads back. The networks are served once discovered, a network failing is
+/// discovered again by the next reload.
+fn discover_networks(
+	config: Config,
+	added: Vec<String>,
+	networks: Networks,
+	discovering: Arc<Mutex<HashSet<String>>>,
+) {
+	let result = thread::Builder::new()
+		.name("network-discovery".to_string())
+		.spawn(move || {
+			let mut runtime = Runtime::new().expect("Failed to start new Runtime");
+			for network in added {
+				match init_network(&mut runtime, &config, &network) {
+					Ok(info) => networks.insert(network.clone(), info),
+					Err(e) => warn!("Failed to discover network {}: {}", network, e),
+				}
+				discovering.lock().expect("qed").remove(&network);
+			}
+		});
+	if let Err(e) = result {
+		warn!("Failed to start network discovery: {:?}", e);
+	}
+}
+
+/// Read the conf file with the overrides applied, a missing conf file is empty
+fn read_config(conf_path: &Path, overrides: &[Override]) -> errors::Result<Config> {
+	let mut str_val = String::new();
+	match File::open(&conf_path) {
+		Ok(mut file) => {
+			file.read_to_string(&mut str_val)?;
+		}
+		Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+		Err(e) => return Err(format!("Failed to open conf file {:?}: {}", conf_path, e).into()),
+	}
+
+	let conf = validation::parse(&str_val, overrides).map_err(|problems| {
+		let problems = problems
+			.iter()
+			.map(|x| format!("  {}", x))
+			.collect::<Vec<_>>();
+		format!(
+			"Invalid conf file {:?}:\n{}",
+			conf_path,
+			problems.join("\n")
+		)
+	})?;
 
 	Ok(conf)
 }
 
-fn init_global(config: &Config) -> errors::Result<()> {
-	init_hrp(config)?;
+fn init_network(
+	runtime: &mut Runtime,
+	config: &Config,
+	network: &str,
+) -> errors::Result<NetworkInfo> {
+	let hrp = init_hrp(runtime, config, network)?;
 
-	init_shard_count(config)?;
+	let shard_count = init_shard_count(runtime, config, network)?;
 
-	Ok(())
+	Ok(NetworkInfo { hrp, shard_count })
 }
 
-fn init_hrp(config: &Config) -> errors::Result<()> {
-	let rpc_client = Arc::new(RpcClient::new(config.clone()));
+fn init_hrp(runtime: &mut Runtime, config: &Config, network: &str) -> errors::Result<Hrp> {
+	let rpc_client = Arc::new(RpcClient::new(
+		SharedConfig::new(config.clone()),
+		network.to_string(),
+	));
 	let future = rpc_client
 		.call_method_async("system_chain", "", (), 0)
 		.unwrap_or_else(|e| Box::new(futures::future::err(e.into())));
 
-	let mut runtime = Runtime::new().expect("Failed to start new Runtime");
 	let result: jsonrpc_core::Result<String> = runtime.block_on(future);
 
 	let result = result.map_err(|_| "Failed to set shard_count")?;
@@ -124,19 +644,31 @@ fn init_hrp(config: &Config) -> errors::Result<()> {
 		"MainNet" => Hrp::MAINNET,
 		_ => Hrp::TESTNET,
 	};
-	info!("set up hrp: {:?}", hrp);
-	HRP.set(hrp).map_err(|_| "Failed to set hrp")?;
+	info!("set up hrp of {}: {:?}", network, hrp);
 
-	Ok(())
+	Ok(hrp)
 }
 
-fn init_shard_count(config: &Config) -> errors::Result<()> {
-	let rpc_client = Arc::new(RpcClient::new(config.clone()));
+fn init_shard_count(runtime: &mut Runtime, config: &Config, network: &str) -> errors::Result<u16> {
+	let shard_count = get_shard_count(runtime, config, network)?;
+
+	check_shard_count(config, network, shard_count)?;
+
+	info!("set up shard_count of {}: {}", network, shard_count);
+
+	Ok(shard_count)
+}
+
+/// Get the shard count from the sharding info in the digest of the best header of shard 0
+fn get_shard_count(runtime: &mut Runtime, config: &Config, network: &str) -> errors::Result<u16> {
+	let rpc_client = Arc::new(RpcClient::new(
+		SharedConfig::new(config.clone()),
+		network.to_string(),
+	));
 	let future = rpc_client
 		.call_method_async("chain_getHeader", "", (), 0)
 		.unwrap_or_else(|e| Box::new(futures::future::err(e.into())));
 
-	let mut runtime = Runtime::new().expect("Failed to start new Runtime");
 	let result: jsonrpc_core::Result<Option<Value>> = runtime.block_on(future);
 
 	let result = result.map_err(|_| "Failed to set shard_count")?;
@@ -157,11 +689,75 @@ fn init_shard_count(config: &Config) -> errors::Result<()> {
 
 	let (_, shard_count) = shard_info.ok_or("Failed to set shard_count".to_string())?;
 
-	info!("set up shard_count: {}", shard_count);
-	SHARD_COUNT
-		.set(shard_count)
-		.map_err(|_| "Failed to set shard_count")?;
+	Ok(shard_count)
+}
 
+/// Get the shard info from the sharding inherent of the best block of shard 0
+///
+/// Blocks without the inherent fall back to the shard count in the digest.
+fn get_shard_info(
+	runtime: &mut Runtime,
+	config: &Config,
+	network: &str,
+) -> errors::Result<ShardInfo> {
+	let rpc_client = Arc::new(RpcClient::new(
+		SharedConfig::new(config.clone()),
+		network.to_string(),
+	));
+	let future = client::get_block_future(rpc_client, &None, 0);
+
+	let block = runtime
+		.block_on(future)
+		.map_err(|_| "Failed to get the best block".to_string())?
+		.ok_or("Failed to get the best block".to_string())?;
+
+	match sharding_inherent(&block.block.extrinsics) {
+		Some(shard_info) => Ok(shard_info),
+		None => Ok(ShardInfo {
+			num: 0,
+			count: get_shard_count(runtime, config, network)?,
+			scale_out: None,
+		}),
+	}
+}
+
+/// Shard info of the sharding inherent among the extrinsics of a block
+fn sharding_inherent(extrinsics: &[String]) -> Option<ShardInfo> {
+	extrinsics.iter().find_map(|extrinsic| {
+		let extrinsic = hex::decode(extrinsic.trim_start_matches("0x")).ok()?;
+		// extrinsics are length prefixed
+		let extrinsic: Vec<u8> = Decode::decode(&mut &extrinsic[..])?;
+		match extrinsic.get(..3) {
+			Some([UNSIGNED_VERSION, SHARDING_MODULE, SET_SHARD_INFO]) => {
+				Decode::decode(&mut &extrinsic[3..])
+			}
+			_ => None,
+		}
+	})
+}
+
+/// All the shards of the chain must be configured
+///
+/// More shards can be configured ahead of a scale out.
+fn check_shard_count(config: &Config, network: &str, shard_count: u16) -> errors::Result<()> {
+	let missing = (0..shard_count)
+		.filter(|shard_num| {
+			config
+				.shards(network)
+				.map(|shards| !shards.contains_key(&shard_num.to_string()))
+				.unwrap_or(true)
+		})
+		.map(|shard_num| shard_num.to_string())
+		.collect::<Vec<_>>();
+	if !missing.is_empty() {
+		return Err(format!(
+			"Invalid conf file: the chain {} has {} shards, missing shards: {}",
+			network,
+			shard_count,
+			missing.join(", ")
+		)
+		.into());
+	}
 	Ok(())
 }
 
@@ -195,6 +791,10 @@ fn get_logs(digest: &Value) -> Result<Vec<DigestItem<Hash, AuthorityId, ()>>, St
 	Ok(logs)
 }
 
+fn conf_file(opt: &Opt, version: &VersionInfo) -> PathBuf {
+	conf_path(&base_path(opt, version)).join("config.toml")
+}
+
 fn conf_path(base_path: &Path) -> PathBuf {
 	let mut path = base_path.to_owned();
 	path.push("conf");
@@ -213,3 +813,76 @@ fn base_path(cli: &Opt, version: &VersionInfo) -> PathBuf {
 		.expect("app directories exist on all supported platforms; qed")
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sharding_inherent() {
+		let extrinsics = vec![
+			// timestamp inherent
+			"0x280102000b30a9c2f37101".to_string(),
+			// signed extrinsics are skipped
+			"0x0c810500".to_string(),
+			"0x200105000000040000".to_string(),
+		];
+		let shard_info = sharding_inherent(&extrinsics).unwrap();
+		assert_eq!(
+			shard_info,
+			ShardInfo {
+				num: 0,
+				count: 4,
+				scale_out: None,
+			}
+		);
+		assert_eq!(shard_info.target_count(), 4);
+
+		let extrinsics = vec!["0x2801050001000400010500".to_string()];
+		let shard_info = sharding_inherent(&extrinsics).unwrap();
+		assert_eq!(
+			shard_info,
+			ShardInfo {
+				num: 1,
+				count: 4,
+				scale_out: Some(ScaleOut { shard_num: 5 }),
+			}
+		);
+		assert_eq!(shard_info.target_count(), 8);
+
+		assert_eq!(sharding_inherent(&["0x0c010400".to_string()]), None);
+		assert_eq!(sharding_inherent(&["0x08010500".to_string()]), None);
+	}
+
+	#[test]
+	fn test_check_shard_count() {
+		let shard = Shard {
+			rpc: vec!["http://127.0.0.1:9933".to_string()],
+		};
+		let config = Config {
+			shards: vec![("0".to_string(), shard.clone()), ("1".to_string(), shard)]
+				.into_iter()
+				.collect(),
+			..Default::default()
+		};
+		assert!(check_shard_count(&config, DEFAULT_NETWORK, 2).is_ok());
+		let e = check_shard_count(&config, DEFAULT_NETWORK, 4).unwrap_err();
+		assert!(e.to_string().ends_with("missing shards: 2, 3"));
+		assert!(check_shard_count(&config, "test", 1).is_err());
+	}
+
+	#[test]
+	fn test_restart_free() {
+		let current = Server {
+			hosts: Some(vec!["rpc.yeefoundation.com".to_string()]),
+			..Default::default()
+		};
+		let new = Server {
+			trusted_proxies: Some(vec!["10.0.0.1".to_string()]),
+			..current.clone()
+		};
+		assert!(restart_free(&current, &new));
+		let new = Server { hosts: None, ..new };
+		assert!(!restart_free(&current, &new));
+	}
+}
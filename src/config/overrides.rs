@@ -0,0 +1,235 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use toml::value::Table;
+use toml::Value;
+
+use crate::opt::Opt;
+
+/// Prefix of the environment variables overriding config keys
+pub const ENV_PREFIX: &str = "YEE_PANEL_";
+
+/// Separator of the key path in the environment variable names
+const ENV_SEPARATOR: &str = "__";
+
+/// Keys holding lists, a plain value is split by commas
+const LIST_KEYS: &[&str] = &[
+	"rpc",
+	"cors",
+	"hosts",
+	"ws_origins",
+	"public_methods",
+	"methods",
+];
+
+/// A config key set from the environment or the command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+	pub path: Vec<String>,
+	pub value: Value,
+}
+
+impl Override {
+	fn new(path: Vec<String>, raw: &str) -> Self {
+		let is_list = path
+			.last()
+			.map(|x| LIST_KEYS.contains(&x.as_str()))
+			.unwrap_or(false);
+		Override {
+			value: parse_value(raw, is_list),
+			path,
+		}
+	}
+
+	pub fn key(&self) -> String {
+		self.path.join(".")
+	}
+}
+
+/// Overrides from the environment and the command line, in the order of precedence
+pub fn get_overrides(opt: &Opt) -> Vec<Override> {
+	let mut overrides = from_env(std::env::vars());
+	overrides.extend(from_opt(opt));
+	overrides
+}
+
+/// Overrides from `YEE_PANEL_<SECTION>__<KEY>` variables,
+/// e.g. `YEE_PANEL_SHARDS__0__RPC` or `YEE_PANEL_ACCESS_LOG__ENABLED`
+///
+/// Variables without the separator are command line options.
+pub fn from_env<I: IntoIterator<Item = (String, String)>>(vars: I) -> Vec<Override> {
+	let mut vars = vars
+		.into_iter()
+		.filter(|(name, _)| name.starts_with(ENV_PREFIX) && name.contains(ENV_SEPARATOR))
+		.collect::<Vec<_>>();
+	vars.sort();
+
+	vars.into_iter()
+		.map(|(name, raw)| {
+			let path = name[ENV_PREFIX.len()..]
+				.split(ENV_SEPARATOR)
+				.map(str::to_lowercase)
+				.collect();
+			Override::new(path, &raw)
+		})
+		.collect()
+}
+
+/// Overrides from `--config KEY=VALUE` and `--shard NUM=URI`
+///
+/// The URIs of the same shard are collected into one list.
+pub fn from_opt(opt: &Opt) -> Vec<Override> {
+	let mut overrides = opt
+		.config
+		.iter()
+		.map(|(key, raw)| Override::new(key.split('.').map(ToString::to_string).collect(), raw))
+		.collect::<Vec<_>>();
+
+	let mut shards: Vec<(u16, Vec<Value>)> = vec![];
+	for (shard_num, uri) in &opt.shard {
+		let uri = Value::String(uri.clone());
+		match shards.iter_mut().find(|(x, _)| x == shard_num) {
+			Some((_, uris)) => uris.push(uri),
+			None => shards.push((*shard_num, vec![uri])),
+		}
+	}
+	overrides.extend(shards.into_iter().map(|(shard_num, uris)| Override {
+		path: vec![
+			"shards".to_string(),
+			shard_num.to_string(),
+			"rpc".to_string(),
+		],
+		value: Value::Array(uris),
+	}));
+
+	overrides
+}
+
+/// Set the overridden keys, creating the tables on the path
+pub fn apply(value: &mut Value, overrides: &[Override]) -> Result<(), String> {
+	for item in overrides {
+		let (key, parents) = match item.path.split_last() {
+			Some(v) => v,
+			None => continue,
+		};
+		let mut table = value
+			.as_table_mut()
+			.ok_or_else(|| format!("{}: expected a table", item.key()))?;
+		for parent in parents {
+			table = table
+				.entry(parent.clone())
+				.or_insert_with(|| Value::Table(Table::new()))
+				.as_table_mut()
+				.ok_or_else(|| format!("{}: {} is not a table", item.key(), parent))?;
+		}
+		table.insert(key.clone(), item.value.clone());
+	}
+	Ok(())
+}
+
+/// Parse a toml value, or take it as a string
+fn parse_value(raw: &str, is_list: bool) -> Value {
+	let value = toml::from_str::<Table>(&format!("value = {}", raw))
+		.ok()
+		.and_then(|mut x| x.remove("value"));
+	match value {
+		Some(value) if !is_list || value.is_array() => value,
+		_ if is_list => Value::Array(
+			raw.split(',')
+				.map(str::trim)
+				.filter(|x| !x.is_empty())
+				.map(|x| Value::String(x.to_string()))
+				.collect(),
+		),
+		_ => Value::String(raw.to_string()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+		vars.iter()
+			.map(|(k, v)| (k.to_string(), v.to_string()))
+			.collect()
+	}
+
+	#[test]
+	fn test_from_env() {
+		let overrides = from_env(vars(&[
+			("YEE_PANEL_SHARDS__0__RPC", "http://a:9033, http://b:9033"),
+			("YEE_PANEL_ACCESS_LOG__ENABLED", "true"),
+			("YEE_PANEL_ACCESS_LOG__FORMAT", "json"),
+			("YEE_PANEL_RPC_PORT", "10055"),
+			("PATH", "/bin"),
+		]));
+		let mut value = Value::Table(Table::new());
+		apply(&mut value, &overrides).unwrap();
+		assert_eq!(
+			value,
+			toml::from_str::<Value>(
+				r#"
+[shards.0]
+rpc = ["http://a:9033", "http://b:9033"]
+
+[access_log]
+enabled = true
+format = "json"
+"#
+			)
+			.unwrap()
+		);
+	}
+
+	#[test]
+	fn test_apply() {
+		let mut value = toml::from_str::<Value>(
+			r#"
+[shards.0]
+rpc = ["http://a:9033"]
+
+[shards.1]
+rpc = ["http://b:9133"]
+"#,
+		)
+		.unwrap();
+		let overrides = vec![
+			Override::new(
+				vec!["shards".into(), "1".into(), "rpc".into()],
+				"http://c:9133",
+			),
+			Override::new(vec!["server".into(), "cors".into()], r#"["https://a"]"#),
+		];
+		apply(&mut value, &overrides).unwrap();
+		assert_eq!(
+			value["shards"]["0"]["rpc"][0].as_str(),
+			Some("http://a:9033")
+		);
+		assert_eq!(
+			value["shards"]["1"]["rpc"][0].as_str(),
+			Some("http://c:9133")
+		);
+		assert_eq!(value["server"]["cors"][0].as_str(), Some("https://a"));
+
+		let overrides = vec![Override::new(
+			vec!["shards".into(), "0".into(), "rpc".into(), "x".into()],
+			"1",
+		)];
+		assert!(apply(&mut value, &overrides).is_err());
+	}
+}
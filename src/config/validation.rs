@@ -0,0 +1,503 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::net::IpAddr;
+
+use toml::value::Table;
+use toml::Value;
+
+use crate::config::overrides::{self, Override};
+use crate::config::{Config, DEFAULT_NETWORK};
+
+const ROOT_KEYS: &[&str] = &[
+	"shards",
+	"networks",
+	"server",
+	"tls",
+	"auth",
+	"access_log",
+	"format",
+	"submit",
+	"batch",
+];
+const NETWORK_KEYS: &[&str] = &["shards"];
+const SHARD_KEYS: &[&str] = &["rpc"];
+const SERVER_KEYS: &[&str] = &["cors", "hosts", "ws_origins", "trusted_proxies"];
+const TLS_KEYS: &[&str] = &["cert", "key"];
+const AUTH_KEYS: &[&str] = &["public_methods", "keys"];
+const AUTH_KEY_KEYS: &[&str] = &["identity", "token", "methods"];
+const ACCESS_LOG_KEYS: &[&str] = &["enabled", "format"];
+const FORMAT_KEYS: &[&str] = &["balance", "decimals"];
+const SUBMIT_KEYS: &[&str] = &["validate"];
+const BATCH_KEYS: &[&str] = &["max_addresses"];
+
+/// A problem found in the conf file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+	/// Line in the conf file, starting from 1
+	pub line: Option<usize>,
+	/// Dotted key path
+	pub key: Option<String>,
+	pub message: String,
+}
+
+impl fmt::Display for Problem {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if let Some(line) = self.line {
+			write!(f, "line {}: ", line)?;
+		}
+		if let Some(key) = &self.key {
+			write!(f, "{}: ", key)?;
+		}
+		write!(f, "{}", self.message)
+	}
+}
+
+/// Parse the conf file, apply the overrides and validate the result,
+/// reporting all the problems found at once
+pub fn parse(source: &str, overrides: &[Override]) -> Result<Config, Vec<Problem>> {
+	let problem = |message| {
+		vec![Problem {
+			line: None,
+			key: None,
+			message,
+		}]
+	};
+
+	let mut value: Value = toml::from_str(source).map_err(|e| problem(e.to_string()))?;
+
+	overrides::apply(&mut value, overrides).map_err(problem)?;
+
+	let mut problems = validate(source, &value);
+
+	match value.try_into::<Config>() {
+		Ok(config) if problems.is_empty() => Ok(config),
+		Ok(_) => Err(problems),
+		Err(e) => {
+			problems.push(Problem {
+				line: None,
+				key: None,
+				message: e.to_string(),
+			});
+			Err(problems)
+		}
+	}
+}
+
+/// Validate the structure of the conf file
+pub fn validate(source: &str, value: &Value) -> Vec<Problem> {
+	let mut validator = Validator {
+		source,
+		problems: vec![],
+	};
+
+	let root = match value.as_table() {
+		Some(root) => root,
+		None => {
+			validator.report("", None, "expected a table".to_string());
+			return validator.problems;
+		}
+	};
+
+	validator.check_keys("", root, ROOT_KEYS);
+
+	// the top level shards can be omitted if other networks are configured
+	let has_networks = root
+		.get("networks")
+		.and_then(Value::as_table)
+		.map(|x| !x.is_empty())
+		.unwrap_or(false);
+	if root.contains_key("shards") || !has_networks {
+		validator.check_shards("shards", root.get("shards"));
+	}
+	validator.check_networks(root.get("networks"));
+
+	let sections = [
+		("server", SERVER_KEYS),
+		("tls", TLS_KEYS),
+		("auth", AUTH_KEYS),
+		("access_log", ACCESS_LOG_KEYS),
+		("format", FORMAT_KEYS),
+		("submit", SUBMIT_KEYS),
+		("batch", BATCH_KEYS),
+	];
+	for (name, keys) in sections.iter() {
+		if let Some(table) = root.get(*name).and_then(Value::as_table) {
+			validator.check_keys(name, table, keys);
+		}
+	}
+
+	let auth_keys = root
+		.get("auth")
+		.and_then(|x| x.get("keys"))
+		.and_then(Value::as_array);
+	if let Some(auth_keys) = auth_keys {
+		for key in auth_keys.iter().filter_map(Value::as_table) {
+			validator.check_keys("auth.keys", key, AUTH_KEY_KEYS);
+		}
+	}
+
+	// amounts of u128 have at most 38 decimal digits
+	let decimals = root.get("format").and_then(|x| x.get("decimals"));
+	if let Some(decimals) = decimals {
+		match decimals.as_integer() {
+			Some(0..=38) => (),
+			_ => validator.report(
+				"format",
+				Some("decimals"),
+				format!("expected an integer from 0 to 38, found {}", decimals),
+			),
+		}
+	}
+
+	let trusted_proxies = root
+		.get("server")
+		.and_then(|x| x.get("trusted_proxies"))
+		.and_then(Value::as_array);
+	for proxy in trusted_proxies.into_iter().flatten() {
+		if proxy
+			.as_str()
+			.and_then(|x| x.parse::<IpAddr>().ok())
+			.is_none()
+		{
+			validator.report(
+				"server",
+				Some("trusted_proxies"),
+				format!("expected an IP address, found {}", proxy),
+			);
+		}
+	}
+
+	let max_addresses = root.get("batch").and_then(|x| x.get("max_addresses"));
+	if let Some(max_addresses) = max_addresses {
+		match max_addresses.as_integer() {
+			Some(x) if x > 0 => (),
+			_ => validator.report(
+				"batch",
+				Some("max_addresses"),
+				format!("expected a positive integer, found {}", max_addresses),
+			),
+		}
+	}
+
+	validator.problems
+}
+
+struct Validator<'a> {
+	source: &'a str,
+	problems: Vec<Problem>,
+}
+
+impl<'a> Validator<'a> {
+	fn report(&mut self, table: &str, key: Option<&str>, message: String) {
+		let path = match key {
+			Some(key) if table.is_empty() => key.to_string(),
+			Some(key) => format!("{}.{}", table, key),
+			None => table.to_string(),
+		};
+		self.problems.push(Problem {
+			line: find_line(self.source, table, key),
+			key: if path.is_empty() { None } else { Some(path) },
+			message,
+		});
+	}
+
+	fn check_keys(&mut self, path: &str, table: &Table, known: &[&str]) {
+		for key in table.keys() {
+			if !known.contains(&key.as_str()) {
+				self.report(
+					path,
+					Some(key),
+					format!("unknown key, expected one of: {}", known.join(", ")),
+				);
+			}
+		}
+	}
+
+	fn check_networks(&mut self, networks: Option<&Value>) {
+		let networks = match networks {
+			Some(Value::Table(networks)) => networks,
+			Some(_) => {
+				self.report("", Some("networks"), "expected a table".to_string());
+				return;
+			}
+			None => return,
+		};
+
+		for (name, network) in networks {
+			let path = format!("networks.{}", name);
+
+			if name == DEFAULT_NETWORK {
+				self.report(
+					&path,
+					None,
+					"reserved name, configure the top level shards instead".to_string(),
+				);
+			}
+
+			match network.as_table() {
+				Some(network) => {
+					self.check_keys(&path, network, NETWORK_KEYS);
+					self.check_shards(&format!("{}.shards", path), network.get("shards"));
+				}
+				None => self.report("networks", Some(name), "expected a table".to_string()),
+			}
+		}
+	}
+
+	fn check_shards(&mut self, shards_path: &str, shards: Option<&Value>) {
+		let shards = match shards {
+			Some(Value::Table(shards)) if !shards.is_empty() => shards,
+			Some(Value::Table(_)) | None => {
+				self.report(shards_path, None, "no shards configured".to_string());
+				return;
+			}
+			Some(_) => {
+				self.report(shards_path, None, "expected a table".to_string());
+				return;
+			}
+		};
+
+		let mut shard_nums = BTreeSet::new();
+
+		for (key, shard) in shards {
+			let path = format!("{}.{}", shards_path, key);
+
+			match key.parse::<u16>() {
+				Ok(shard_num) => {
+					shard_nums.insert(shard_num);
+				}
+				Err(_) => self.report(&path, None, "shard number must be a number".to_string()),
+			}
+
+			let shard = match shard.as_table() {
+				Some(shard) => shard,
+				None => {
+					self.report(shards_path, Some(key), "expected a table".to_string());
+					continue;
+				}
+			};
+
+			self.check_keys(&path, shard, SHARD_KEYS);
+
+			let rpc = match shard.get("rpc") {
+				Some(Value::Array(rpc)) => rpc,
+				Some(_) => {
+					self.report(&path, Some("rpc"), "expected a list of URIs".to_string());
+					continue;
+				}
+				None => {
+					self.report(&path, None, "missing rpc".to_string());
+					continue;
+				}
+			};
+
+			if rpc.is_empty() {
+				self.report(&path, Some("rpc"), "rpc list is empty".to_string());
+			}
+
+			for uri in rpc {
+				match uri.as_str() {
+					Some(uri) => {
+						if let Err(e) = check_uri(uri) {
+							self.report(
+								&path,
+								Some("rpc"),
+								format!("malformed URI {:?}: {}", uri, e),
+							);
+						}
+					}
+					None => {
+						self.report(&path, Some("rpc"), format!("expected a URI, found {}", uri))
+					}
+				}
+			}
+		}
+
+		let missing = (0..shard_nums.len() as u16)
+			.filter(|x| !shard_nums.contains(x))
+			.map(|x| x.to_string())
+			.collect::<Vec<_>>();
+		if !missing.is_empty() {
+			self.report(
+				shards_path,
+				None,
+				format!(
+					"shard numbers must be contiguous from 0, missing: {}",
+					missing.join(", ")
+				),
+			);
+		}
+	}
+}
+
+fn check_uri(uri: &str) -> Result<(), String> {
+	let uri = uri.parse::<http::hyper::Uri>().map_err(|e| e.to_string())?;
+	match uri.scheme_str() {
+		Some("http") | Some("https") => (),
+		_ => return Err("expected http:// or https://".to_string()),
+	}
+	if uri.host().is_none() {
+		return Err("missing host".to_string());
+	}
+	Ok(())
+}
+
+/// Find the line of the table header, or of the key in the table
+fn find_line(source: &str, table: &str, key: Option<&str>) -> Option<usize> {
+	let mut current = String::new();
+	for (i, line) in source.lines().enumerate() {
+		let line = line.trim();
+		if line.starts_with('[') {
+			current = line
+				.trim_matches(|c| c == '[' || c == ']')
+				.split('.')
+				.map(|x| x.trim().trim_matches('"'))
+				.collect::<Vec<_>>()
+				.join(".");
+			if key.is_none() && current == table {
+				return Some(i + 1);
+			}
+			continue;
+		}
+		if let Some(key) = key {
+			let is_key = line.starts_with(key) && line[key.len()..].trim_start().starts_with('=');
+			if current == table && is_key {
+				return Some(i + 1);
+			}
+		}
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn problems(source: &str) -> Vec<String> {
+		match parse(source, &[]) {
+			Ok(_) => vec![],
+			Err(problems) => problems.iter().map(ToString::to_string).collect(),
+		}
+	}
+
+	#[test]
+	fn test_valid() {
+		let source = r#"
+[shards.0]
+rpc = ["http://127.0.0.1:9033"]
+
+[shards.1]
+rpc = ["http://127.0.0.1:9133", "https://node.example.com"]
+
+[access_log]
+enabled = true
+"#;
+		let config = parse(source, &[]).unwrap();
+		assert_eq!(config.shards.len(), 2);
+		assert!(config.access_log.enabled);
+	}
+
+	#[test]
+	fn test_problems() {
+		let source = r#"
+[shards.0]
+rpc = ["http://127.0.0.1:9033"]
+
+[shards.2]
+rpc = []
+
+[shards.x]
+rpc = ["127.0.0.1:9333"]
+url = "http://127.0.0.1:9333"
+
+[server]
+cors = ["*"]
+port = 80
+"#;
+		assert_eq!(
+			problems(source),
+			vec![
+				"line 6: shards.2.rpc: rpc list is empty",
+				"line 8: shards.x: shard number must be a number",
+				"line 10: shards.x.url: unknown key, expected one of: rpc",
+				"line 9: shards.x.rpc: malformed URI \"127.0.0.1:9333\": expected http:// or https://",
+				"shards: shard numbers must be contiguous from 0, missing: 1",
+				"line 14: server.port: unknown key, expected one of: cors, hosts, ws_origins, trusted_proxies",
+			]
+		);
+	}
+
+	#[test]
+	fn test_networks() {
+		let source = r#"
+[networks.testnet.shards.0]
+rpc = ["http://127.0.0.1:19033"]
+
+[networks.testnet.shards.2]
+rpc = ["http://127.0.0.1:19233"]
+"#;
+		assert_eq!(
+			problems(source),
+			vec!["networks.testnet.shards: shard numbers must be contiguous from 0, missing: 1"]
+		);
+	}
+
+	#[test]
+	fn test_trusted_proxies() {
+		let source = r#"
+[shards.0]
+rpc = ["http://127.0.0.1:9033"]
+
+[server]
+trusted_proxies = ["10.0.0.1", "::1", "10.0.0.0/8"]
+"#;
+		assert_eq!(
+			problems(source),
+			vec!["line 6: server.trusted_proxies: expected an IP address, found \"10.0.0.0/8\""]
+		);
+	}
+
+	#[test]
+	fn test_batch() {
+		let source = r#"
+[shards.0]
+rpc = ["http://127.0.0.1:9033"]
+
+[batch]
+max_addresses = 0
+"#;
+		assert_eq!(
+			problems(source),
+			vec!["line 6: batch.max_addresses: expected a positive integer, found 0"]
+		);
+	}
+
+	#[test]
+	fn test_no_shards() {
+		assert_eq!(problems(""), vec!["shards: no shards configured"]);
+	}
+
+	#[test]
+	fn test_syntax_error() {
+		let problems = problems("[shards.0\nrpc = []");
+		assert_eq!(problems.len(), 1);
+		assert!(problems[0].contains("line 1"));
+	}
+}
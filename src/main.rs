@@ -15,25 +15,26 @@
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
-use structopt::StructOpt;
-
-use crate::config::{get_config, VersionInfo};
+use crate::config::{
+	get_config, init_networks, track_shard_count, watch_config, SharedConfig, VersionInfo,
+};
 
 mod config;
 mod errors;
 mod opt;
 mod rpc;
+mod watch;
 
 fn main() {
 	let result = run();
 	match result {
 		Ok(_) => (),
-		Err(e) => eprintln!("{:?}", e),
+		Err(e) => eprintln!("{}", e),
 	}
 }
 
 fn run() -> errors::Result<()> {
-	let opt = opt::Opt::from_args();
+	let opt = opt::Opt::from_args_and_env();
 
 	init_logger(&opt.log)?;
 
@@ -45,7 +46,15 @@ fn run() -> errors::Result<()> {
 
 	let config = get_config(&opt, &version_info)?;
 
-	rpc::run(&opt, &config)?;
+	let networks = init_networks(&config)?;
+
+	let config = SharedConfig::new(config);
+
+	watch_config(&opt, &version_info, config.clone(), networks.clone());
+
+	track_shard_count(config.clone(), networks.clone());
+
+	rpc::run(&opt, config, networks)?;
 
 	Ok(())
 }
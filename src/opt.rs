@@ -25,31 +25,144 @@ pub const DEFAULT_WS_PORT: u16 = 10066;
 #[derive(Debug, StructOpt, Clone)]
 pub struct Opt {
 	/// Specify HTTP RPC server TCP port
-	#[structopt(long = "rpc-port", value_name = "PORT")]
+	#[structopt(long = "rpc-port", value_name = "PORT", env = "YEE_PANEL_RPC_PORT")]
 	pub rpc_port: Option<u16>,
 
 	/// Specify WebSockets RPC server TCP port
-	#[structopt(long = "ws-port", value_name = "PORT")]
+	#[structopt(long = "ws-port", value_name = "PORT", env = "YEE_PANEL_WS_PORT")]
 	pub ws_port: Option<u16>,
 
-	/// Listen to all RPC interfaces (default is local)
+	/// Listen to all RPC interfaces (default is local).
+	/// Also enabled by YEE_PANEL_RPC_EXTERNAL=true
 	#[structopt(long = "rpc-external")]
 	pub rpc_external: bool,
 
-	/// Listen to all Websocket interfaces (default is local)
+	/// Listen to all Websocket interfaces (default is local).
+	/// Also enabled by YEE_PANEL_WS_EXTERNAL=true
 	#[structopt(long = "ws-external")]
 	pub ws_external: bool,
 
+	/// Specify browser Origins allowed to access the HTTP RPC server.
+	/// A comma separated list of origins, or "all" to disable validation
+	#[structopt(
+		long = "rpc-cors",
+		value_name = "ORIGINS",
+		env = "YEE_PANEL_RPC_CORS",
+		parse(try_from_str = parse_domains)
+	)]
+	pub rpc_cors: Option<Domains>,
+
+	/// Specify Host headers allowed by the HTTP and WS RPC servers.
+	/// A comma separated list of hosts, or "all" to disable validation
+	#[structopt(
+		long = "rpc-hosts",
+		value_name = "HOSTS",
+		env = "YEE_PANEL_RPC_HOSTS",
+		parse(try_from_str = parse_domains)
+	)]
+	pub rpc_hosts: Option<Domains>,
+
+	/// Specify browser Origins allowed to access the WS RPC server.
+	/// A comma separated list of origins, or "all" to disable validation
+	#[structopt(
+		long = "ws-origins",
+		value_name = "ORIGINS",
+		env = "YEE_PANEL_WS_ORIGINS",
+		parse(try_from_str = parse_domains)
+	)]
+	pub ws_origins: Option<Domains>,
+
 	/// Specify custom base path.
 	#[structopt(
 		long = "base-path",
 		short = "b",
 		value_name = "PATH",
+		env = "YEE_PANEL_BASE_PATH",
 		parse(from_os_str)
 	)]
 	pub base_path: Option<PathBuf>,
 
 	/// Sets a custom logging filter
-	#[structopt(short = "l", long = "log", value_name = "LOG_PATTERN")]
+	#[structopt(
+		short = "l",
+		long = "log",
+		value_name = "LOG_PATTERN",
+		env = "YEE_PANEL_LOG"
+	)]
 	pub log: Option<String>,
+
+	/// Specify an RPC URI of a shard, e.g. 0=http://127.0.0.1:9033.
+	/// Can be repeated, replaces the URIs of the shard in the conf file
+	#[structopt(
+		long = "shard",
+		value_name = "NUM=URI",
+		number_of_values = 1,
+		parse(try_from_str = parse_shard)
+	)]
+	pub shard: Vec<(u16, String)>,
+
+	/// Set a config key, e.g. access_log.enabled=true.
+	/// Can be repeated
+	#[structopt(
+		long = "config",
+		value_name = "KEY=VALUE",
+		number_of_values = 1,
+		parse(try_from_str = parse_key_value)
+	)]
+	pub config: Vec<(String, String)>,
+}
+
+impl Opt {
+	/// Parse the command line, flags can also be enabled by environment variables
+	pub fn from_args_and_env() -> Self {
+		let mut opt = Self::from_args();
+		opt.rpc_external |= env_flag("YEE_PANEL_RPC_EXTERNAL");
+		opt.ws_external |= env_flag("YEE_PANEL_WS_EXTERNAL");
+		opt
+	}
+}
+
+/// Allowed domains, `None` means all
+#[derive(Debug, Clone)]
+pub struct Domains(pub Option<Vec<String>>);
+
+fn env_flag(name: &str) -> bool {
+	match std::env::var(name) {
+		Ok(value) => value == "1" || value.eq_ignore_ascii_case("true"),
+		Err(_) => false,
+	}
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+	let mut pair = s.splitn(2, '=');
+	match (pair.next(), pair.next()) {
+		(Some(key), Some(value)) if !key.trim().is_empty() => {
+			Ok((key.trim().to_string(), value.trim().to_string()))
+		}
+		_ => Err(format!("Expected KEY=VALUE: {}", s)),
+	}
+}
+
+fn parse_shard(s: &str) -> Result<(u16, String), String> {
+	let (shard_num, uri) = parse_key_value(s)?;
+	let shard_num = shard_num
+		.parse()
+		.map_err(|_| format!("Invalid shard number: {}", shard_num))?;
+	Ok((shard_num, uri))
+}
+
+fn parse_domains(s: &str) -> Result<Domains, String> {
+	if s == "all" {
+		return Ok(Domains(None));
+	}
+	let domains = s
+		.split(',')
+		.map(str::trim)
+		.filter(|x| !x.is_empty())
+		.map(ToString::to_string)
+		.collect::<Vec<_>>();
+	if domains.is_empty() {
+		return Err(format!("Invalid domain list: {}", s));
+	}
+	Ok(Domains(Some(domains)))
 }
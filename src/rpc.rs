@@ -20,20 +20,35 @@ use std::net::SocketAddr;
 use futures::future::Future;
 use log::info;
 
-use crate::config::Config;
-use crate::opt::{Opt, DEFAULT_RPC_PORT, DEFAULT_WS_PORT};
+use crate::config::{Config, Networks, Server, SharedConfig};
+use crate::opt::{Domains, Opt, DEFAULT_RPC_PORT, DEFAULT_WS_PORT};
+use crate::rpc::address::{Address, AddressApi};
+use crate::rpc::auth::Authenticator;
 use crate::rpc::chain::{Chain, ChainApi};
+use crate::rpc::middleware::RpcMiddleware;
 use crate::rpc::server::{start_http, start_ws};
+use crate::rpc::system::{System, SystemApi};
+use crate::rpc::tls::{start_proxy, Certificates};
 
+mod access_log;
+mod address;
+mod auth;
+mod builder;
 mod chain;
 pub mod client;
+mod context;
+mod decoder;
 pub mod errors;
 mod metadata;
+mod metrics;
+mod middleware;
 mod serde;
 mod server;
+mod system;
+mod tls;
 mod types;
 
-pub fn run(opt: &Opt, config: &Config) -> errors::Result<()> {
+pub fn run(opt: &Opt, config: SharedConfig, networks: Networks) -> errors::Result<()> {
 	let rpc_interface: &str = if opt.rpc_external {
 		"0.0.0.0"
 	} else {
@@ -57,20 +72,88 @@ pub fn run(opt: &Opt, config: &Config) -> errors::Result<()> {
 	let (signal, exit) = exit_future::signal();
 
 	let handler = || {
-		let chain = Chain::new(config.clone());
+		let chain = Chain::new(config.clone(), networks.clone());
+		let system = System::new(config.clone(), networks.clone());
+		let address = Address::new(networks.clone());
 
-		let mut io = pubsub::PubSubHandler::default();
+		let mut io = pubsub::PubSubHandler::new(jsonrpc_core::MetaIoHandler::with_middleware(
+			RpcMiddleware::new(config.clone()),
+		));
 		io.extend_with(chain.to_delegate());
+		io.extend_with(system.to_delegate());
+		io.extend_with(address.to_delegate());
 		io
 	};
 
-	let _server = start_http(&rpc_address_http, handler())?;
+	let server = server_config(opt, &config.get());
 
-	info!("Switch rpc http listen on: {}", rpc_address_http);
+	let authenticator = Authenticator::new(config.clone());
 
-	let _server = start_ws(&rpc_address_ws, handler())?;
+	let certificates = match &config.get().tls {
+		Some(tls) => Some(Certificates::load(tls)?),
+		None => None,
+	};
+
+	// with tls, the servers listen on local ephemeral ports behind the tls proxies
+	let local_address = || parse_address("127.0.0.1:0", None);
 
-	info!("Switch rpc ws listen on: {}", rpc_address_ws);
+	let http_address = match certificates {
+		Some(_) => local_address()?,
+		None => rpc_address_http,
+	};
+	let http_server = start_http(
+		&http_address,
+		&server,
+		authenticator.clone(),
+		networks.clone(),
+		config.clone(),
+		handler(),
+	)?;
+	let _http_proxy = match &certificates {
+		Some(certificates) => Some(start_proxy(
+			&rpc_address_http,
+			*http_server.address(),
+			certificates.clone(),
+		)?),
+		None => None,
+	};
+
+	info!(
+		"Switch rpc {} listen on: {}",
+		if certificates.is_some() {
+			"https"
+		} else {
+			"http"
+		},
+		rpc_address_http
+	);
+
+	let ws_address = match certificates {
+		Some(_) => local_address()?,
+		None => rpc_address_ws,
+	};
+	let ws_server = start_ws(
+		&ws_address,
+		&server,
+		authenticator,
+		networks.clone(),
+		config.clone(),
+		handler(),
+	)?;
+	let _ws_proxy = match &certificates {
+		Some(certificates) => Some(start_proxy(
+			&rpc_address_ws,
+			*ws_server.addr(),
+			certificates.clone(),
+		)?),
+		None => None,
+	};
+
+	info!(
+		"Switch rpc {} listen on: {}",
+		if certificates.is_some() { "wss" } else { "ws" },
+		rpc_address_ws
+	);
 
 	exit.wait().unwrap();
 
@@ -79,6 +162,22 @@ pub fn run(opt: &Opt, config: &Config) -> errors::Result<()> {
 	Ok(())
 }
 
+/// Server config with cli options applied
+fn server_config(opt: &Opt, config: &Config) -> Server {
+	let mut server = config.server.clone();
+	let domains = |x: &Domains| x.0.clone();
+	if let Some(cors) = opt.rpc_cors.as_ref().map(domains) {
+		server.cors = cors;
+	}
+	if let Some(hosts) = opt.rpc_hosts.as_ref().map(domains) {
+		server.hosts = hosts;
+	}
+	if let Some(ws_origins) = opt.ws_origins.as_ref().map(domains) {
+		server.ws_origins = ws_origins;
+	}
+	server
+}
+
 fn parse_address(address: &str, port: Option<u16>) -> errors::Result<SocketAddr> {
 	let mut address: SocketAddr = address
 		.parse()
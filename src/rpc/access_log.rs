@@ -0,0 +1,105 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io::Write;
+
+use jsonrpc_core::Params;
+use log::info;
+use serde::Serialize;
+
+use crate::config::{AccessLogFormat, SharedConfig};
+
+const MAX_PARAMS_LEN: usize = 256;
+
+#[derive(Serialize, Debug)]
+pub struct AccessLogEntry {
+	pub request_id: String,
+	pub client_ip: Option<String>,
+	pub transport: Option<String>,
+	pub identity: Option<String>,
+	pub network: String,
+	pub method: String,
+	pub params: String,
+	pub shards: Vec<u16>,
+	pub upstream_uris: Vec<String>,
+	pub upstream_calls: usize,
+	pub latency_ms: u64,
+	pub error_code: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct AccessLogger {
+	config: SharedConfig,
+}
+
+impl AccessLogger {
+	pub fn new(config: SharedConfig) -> Self {
+		AccessLogger { config }
+	}
+
+	pub fn enabled(&self) -> bool {
+		self.config.get().access_log.enabled
+	}
+
+	pub fn log(&self, entry: &AccessLogEntry) {
+		let config = self.config.get();
+		if !config.access_log.enabled {
+			return;
+		}
+		match config.access_log.format {
+			AccessLogFormat::Text => info!(
+				target: "access",
+				"{} {} {} {} {} {} {} shards={:?} upstream_uris={:?} upstream_calls={} latency_ms={} error_code={}",
+				entry.request_id,
+				entry.client_ip.as_ref().map(AsRef::as_ref).unwrap_or("-"),
+				entry.transport.as_ref().map(AsRef::as_ref).unwrap_or("-"),
+				entry.identity.as_ref().map(AsRef::as_ref).unwrap_or("-"),
+				entry.network,
+				entry.method,
+				entry.params,
+				entry.shards,
+				entry.upstream_uris,
+				entry.upstream_calls,
+				entry.latency_ms,
+				entry
+					.error_code
+					.map(|x| x.to_string())
+					.unwrap_or_else(|| "-".to_string()),
+			),
+			AccessLogFormat::Json => {
+				if let Ok(line) = serde_json::to_string(entry) {
+					let stdout = std::io::stdout();
+					let _ = writeln!(stdout.lock(), "{}", line);
+				}
+			}
+		}
+	}
+}
+
+/// Params as json, truncated to `MAX_PARAMS_LEN` bytes
+pub fn summarize_params(params: &Params) -> String {
+	let mut params = serde_json::to_string(params).unwrap_or_default();
+	if params.len() > MAX_PARAMS_LEN {
+		let mut end = MAX_PARAMS_LEN;
+		while !params.is_char_boundary(end) {
+			end -= 1;
+		}
+		params.truncate(end);
+		params.push_str("...");
+	}
+	params
+}
@@ -0,0 +1,145 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use futures::future;
+use jsonrpc_core::BoxFuture;
+use jsonrpc_derive::rpc;
+use serde_json::{json, Value};
+use yee_primitives::AddressCodec;
+use yee_sharding_primitives::utils::shard_num_for_bytes;
+
+use crate::config::{NetworkInfo, Networks};
+use crate::rpc::context;
+use crate::rpc::errors;
+use crate::rpc::serde::Hex;
+use crate::rpc::types::public_to_address;
+
+#[rpc]
+pub trait AddressApi {
+	/// Whether the address has a valid checksum and the HRP of the network
+	#[rpc(name = "address_validate")]
+	fn validate(&self, address: String) -> BoxFuture<Value>;
+
+	#[rpc(name = "address_getShard")]
+	fn get_shard(&self, address: String) -> BoxFuture<u16>;
+
+	#[rpc(name = "address_fromPublicKey")]
+	fn from_public_key(&self, public_key: Hex<Vec<u8>>) -> BoxFuture<String>;
+
+	#[rpc(name = "address_toPublicKey")]
+	fn to_public_key(&self, address: String) -> BoxFuture<Hex<Vec<u8>>>;
+}
+
+pub struct Address {
+	networks: Networks,
+}
+
+impl Address {
+	/// Create new Address API RPC handler.
+	pub fn new(networks: Networks) -> Self {
+		Self { networks }
+	}
+
+	fn network(&self) -> errors::Result<NetworkInfo> {
+		let name = context::network();
+		self.networks
+			.get(&name)
+			.ok_or_else(|| errors::ErrorKind::UnknownNetwork(name).into())
+	}
+}
+
+impl AddressApi for Address {
+	fn validate(&self, address: String) -> BoxFuture<Value> {
+		let result = self
+			.network()
+			.map(|network| match check_address(&address, &network) {
+				Ok(_) => json!({ "valid": true }),
+				Err(reason) => json!({ "valid": false, "reason": reason }),
+			});
+		Box::new(future::result(result.map_err(Into::into)))
+	}
+
+	fn get_shard(&self, address: String) -> BoxFuture<u16> {
+		let result = self.network().and_then(|network| {
+			let public_key = public_key_of(&address, &network)?;
+			shard_num_for_bytes(&public_key, network.shard_count)
+				.ok_or_else(|| errors::ErrorKind::InvalidShard(None, network.shard_count).into())
+		});
+		Box::new(future::result(result.map_err(Into::into)))
+	}
+
+	fn from_public_key(&self, public_key: Hex<Vec<u8>>) -> BoxFuture<String> {
+		let result = self
+			.network()
+			.and_then(|network| public_to_address(&public_key.0, &network))
+			.map(|(address, _shard_num)| address);
+		Box::new(future::result(result.map_err(Into::into)))
+	}
+
+	fn to_public_key(&self, address: String) -> BoxFuture<Hex<Vec<u8>>> {
+		let result = self
+			.network()
+			.and_then(|network| public_key_of(&address, &network))
+			.map(|public_key| Hex(public_key.to_vec()));
+		Box::new(future::result(result.map_err(Into::into)))
+	}
+}
+
+/// Public key of an address of the network
+pub fn public_key_of(address: &str, network: &NetworkInfo) -> errors::Result<[u8; 32]> {
+	check_address(address, network)
+		.map_err(|_| errors::ErrorKind::InvalidAddress(address.to_string()).into())
+}
+
+fn check_address(address: &str, network: &NetworkInfo) -> Result<[u8; 32], &'static str> {
+	let (public_key, hrp) = <[u8; 32]>::from_address(&yee_primitives::Address(address.to_string()))
+		.map_err(|_| "invalid encoding or checksum")?;
+	if hrp != network.hrp {
+		return Err("address of another network");
+	}
+	Ok(public_key)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use yee_primitives::Hrp;
+
+	#[test]
+	fn test_check_address() {
+		let mainnet = NetworkInfo {
+			hrp: Hrp::MAINNET,
+			shard_count: 4,
+		};
+		let testnet = NetworkInfo {
+			hrp: Hrp::TESTNET,
+			shard_count: 4,
+		};
+		let address = "yee1x6c3d0x7la4lvdfee63u4lwepw6n6m0sgwew77gaydxf9jjaaqzqzzu8dj";
+
+		let public_key = check_address(address, &mainnet).unwrap();
+		assert_eq!(public_to_address(&public_key, &mainnet).unwrap().0, address);
+		assert_eq!(
+			check_address(address, &testnet),
+			Err("address of another network")
+		);
+		assert_eq!(
+			check_address(&address.replace("8dj", "8dk"), &mainnet),
+			Err("invalid encoding or checksum")
+		);
+	}
+}
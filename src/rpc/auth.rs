@@ -0,0 +1,196 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config::SharedConfig;
+use crate::rpc::errors;
+
+/// Methods that can always be called without a key, such as the health probe
+const ALWAYS_PUBLIC_METHODS: &[&str] = &["system_health"];
+
+/// Resolves API keys to identities and checks method allow-lists.
+///
+/// Everything is allowed if no `[auth]` section is configured.
+#[derive(Clone)]
+pub struct Authenticator {
+	config: SharedConfig,
+}
+
+impl Authenticator {
+	pub fn new(config: SharedConfig) -> Self {
+		Authenticator { config }
+	}
+
+	/// Get the identity of the given token.
+	///
+	/// No token means anonymous, an unknown token is an error.
+	pub fn identify(&self, token: Option<&str>) -> errors::Result<Option<String>> {
+		let config = self.config.get();
+		let (auth, token) = match (&config.auth, token) {
+			(Some(auth), Some(token)) => (auth, token),
+			_ => return Ok(None),
+		};
+
+		// every key is compared, the time taken does not tell how close a token is
+		auth.keys
+			.iter()
+			.filter(|key| constant_time_eq(key.token.as_bytes(), token.as_bytes()))
+			.fold(None, |found, key| found.or(Some(key)))
+			.map(|key| Some(key.identity.clone()))
+			.ok_or(errors::ErrorKind::Unauthorized.into())
+	}
+
+	pub fn is_allowed(&self, identity: Option<&str>, method: &str) -> bool {
+		let config = self.config.get();
+		let auth = match &config.auth {
+			Some(auth) => auth,
+			None => return true,
+		};
+
+		if ALWAYS_PUBLIC_METHODS.contains(&method) || matches_any(&auth.public_methods, method) {
+			return true;
+		}
+
+		match identity {
+			Some(identity) => auth
+				.keys
+				.iter()
+				.filter(|key| key.identity == identity)
+				.any(|key| matches_any(&key.methods, method)),
+			None => false,
+		}
+	}
+}
+
+/// Compare in a time independent of the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	if a.len() != b.len() {
+		return false;
+	}
+	a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn matches_any(patterns: &[String], method: &str) -> bool {
+	patterns.iter().any(|pattern| {
+		if pattern.ends_with('*') {
+			method.starts_with(pattern.trim_end_matches('*'))
+		} else {
+			pattern == method
+		}
+	})
+}
+
+/// Get the bearer token from the value of an `Authorization` header
+pub fn bearer_token(header: &str) -> Option<&str> {
+	let header = header.trim();
+	if header.len() > 7 && header[..7].eq_ignore_ascii_case("bearer ") {
+		Some(header[7..].trim())
+	} else {
+		None
+	}
+}
+
+/// Get the `token` parameter from the query string of a resource
+pub fn query_token(resource: &str) -> Option<&str> {
+	query_param(resource, "token")
+}
+
+/// Get a parameter from the query string of a resource
+pub fn query_param<'a>(resource: &'a str, name: &str) -> Option<&'a str> {
+	let query = resource.splitn(2, '?').nth(1)?;
+	query
+		.split('&')
+		.filter_map(|pair| {
+			let mut pair = pair.splitn(2, '=');
+			match (pair.next(), pair.next()) {
+				(Some(key), Some(value)) if key == name => Some(value),
+				_ => None,
+			}
+		})
+		.next()
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::config::{Auth, AuthKey, Config};
+
+	use super::*;
+
+	fn authenticator() -> Authenticator {
+		Authenticator::new(SharedConfig::new(Config {
+			auth: Some(Auth {
+				public_methods: vec!["chain_*".to_string(), "state_getNonce".to_string()],
+				keys: vec![AuthKey {
+					identity: "partner".to_string(),
+					token: "secret".to_string(),
+					methods: vec!["author_submitExtrinsic".to_string()],
+				}],
+			}),
+			..Default::default()
+		}))
+	}
+
+	#[test]
+	fn test_identify() {
+		let auth = authenticator();
+		assert_eq!(auth.identify(None).unwrap(), None);
+		assert_eq!(
+			auth.identify(Some("secret")).unwrap(),
+			Some("partner".to_string())
+		);
+		assert!(auth.identify(Some("other")).is_err());
+		assert!(auth.identify(Some("secreT")).is_err());
+		assert!(auth.identify(Some("secret2")).is_err());
+		assert!(auth.identify(Some("")).is_err());
+
+		let auth = Authenticator::new(SharedConfig::default());
+		assert_eq!(auth.identify(Some("other")).unwrap(), None);
+	}
+
+	#[test]
+	fn test_constant_time_eq() {
+		assert!(constant_time_eq(b"secret", b"secret"));
+		assert!(constant_time_eq(b"", b""));
+		assert!(!constant_time_eq(b"secret", b"Secret"));
+		assert!(!constant_time_eq(b"secret", b"secreT"));
+		assert!(!constant_time_eq(b"secret", b"secret "));
+	}
+
+	#[test]
+	fn test_is_allowed() {
+		let auth = authenticator();
+		assert!(auth.is_allowed(None, "chain_getBestNumber"));
+		assert!(auth.is_allowed(None, "state_getNonce"));
+		assert!(!auth.is_allowed(None, "state_getBalance"));
+		assert!(!auth.is_allowed(None, "author_submitExtrinsic"));
+		assert!(auth.is_allowed(Some("partner"), "author_submitExtrinsic"));
+		assert!(!auth.is_allowed(Some("partner"), "state_getBalance"));
+		assert!(auth.is_allowed(None, "system_health"));
+
+		let auth = Authenticator::new(SharedConfig::default());
+		assert!(auth.is_allowed(None, "author_submitExtrinsic"));
+	}
+
+	#[test]
+	fn test_tokens() {
+		assert_eq!(bearer_token("Bearer secret"), Some("secret"));
+		assert_eq!(bearer_token("bearer  secret "), Some("secret"));
+		assert_eq!(bearer_token("Basic secret"), None);
+		assert_eq!(query_token("/?token=secret"), Some("secret"));
+		assert_eq!(query_token("/?a=1&token=secret"), Some("secret"));
+		assert_eq!(query_token("/"), None);
+	}
+}
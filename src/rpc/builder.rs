@@ -0,0 +1,316 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use parity_codec::{Compact, Decode, Encode};
+use serde::{Deserialize, Serialize};
+use substrate_primitives::blake2_256;
+use substrate_primitives::crypto::Pair as PairT;
+use substrate_primitives::sr25519;
+use yee_signer::tx::call::{balances, Call};
+use yee_signer::tx::types::{Address, Era, Transaction};
+
+use crate::rpc::errors;
+use crate::rpc::serde::Hex;
+use crate::rpc::types::{BlockNumber, Nonce};
+
+/// Blocks a transaction is valid for if the era is not given
+pub const DEFAULT_PERIOD: u64 = 64;
+
+const MIN_PERIOD: u64 = 4;
+
+const MAX_PERIOD: u64 = 1 << 16;
+
+/// Payloads longer than this are hashed before signing
+const MAX_PAYLOAD_LEN: usize = 256;
+
+/// Index of an address which is an account id rather than an account index
+const ACCOUNT_ID_INDEX: u8 = 0xFF;
+
+/// A transaction to be signed by the sender, as the encoded parts of the extrinsic
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnsignedTransaction {
+	/// Encoded sender address
+	pub sender: Hex<Vec<u8>>,
+	pub nonce: Nonce,
+	/// Encoded era
+	pub era: Hex<Vec<u8>>,
+	/// Encoded call
+	pub call: Hex<Vec<u8>>,
+}
+
+impl UnsignedTransaction {
+	pub fn new(sender: &Address, nonce: Nonce, era: &Era, call: &Call) -> Self {
+		Self {
+			sender: Hex(sender.encode()),
+			nonce,
+			era: Hex(era.encode()),
+			call: Hex(call.encode()),
+		}
+	}
+
+	/// What the sender signs, the checkpoint hash being the hash of the birth block of the era
+	pub fn signing_payload(&self, checkpoint_hash: &[u8]) -> Vec<u8> {
+		let mut payload = Compact(self.nonce).encode();
+		payload.extend_from_slice(&self.call.0);
+		payload.extend_from_slice(&self.era.0);
+		payload.extend_from_slice(checkpoint_hash);
+		if payload.len() > MAX_PAYLOAD_LEN {
+			payload = blake2_256(&payload).to_vec();
+		}
+		payload
+	}
+
+	/// Raw extrinsic with the signature of the signing payload
+	pub fn assemble(&self, signature: &[u8]) -> errors::Result<Vec<u8>> {
+		if signature.len() != 64 {
+			return Err(errors::ErrorKind::InvalidExtrinsic(format!(
+				"signature: expected 64 bytes, got {}",
+				signature.len()
+			))
+			.into());
+		}
+		let mut raw_signature = [0u8; 64];
+		raw_signature.copy_from_slice(signature);
+		let tx = Transaction {
+			signature: Some((
+				decode_part("sender", &self.sender.0)?,
+				raw_signature,
+				Compact(self.nonce),
+				decode_part("era", &self.era.0)?,
+			)),
+			call: decode_part("call", &self.call.0)?,
+		};
+		Ok(tx.encode())
+	}
+}
+
+/// The transaction, the signature and the era of a signed extrinsic
+pub fn split_signed(tx: Transaction) -> Option<(UnsignedTransaction, Vec<u8>, Era)> {
+	let (sender, signature, nonce, era) = tx.signature?;
+	let unsigned = UnsignedTransaction::new(&sender, nonce.0, &era, &tx.call);
+	Some((unsigned, signature.to_vec(), era))
+}
+
+/// Whether the signature of the signing payload is valid for the public key
+pub fn verify_signature(payload: &[u8], signature: &[u8], public: &[u8; 32]) -> bool {
+	if signature.len() != 64 {
+		return false;
+	}
+	let mut raw_signature = [0u8; 64];
+	raw_signature.copy_from_slice(signature);
+	sr25519::Pair::verify(
+		&sr25519::Signature::from_raw(raw_signature),
+		payload,
+		&sr25519::Public::from_raw(*public),
+	)
+}
+
+/// Address of an account id
+pub fn account_address(public: &[u8; 32]) -> Address {
+	let encoded = (ACCOUNT_ID_INDEX, *public).encode();
+	Decode::decode(&mut &encoded[..]).expect("qed")
+}
+
+/// Balances transfer call
+pub fn transfer_call(dest: &[u8; 32], value: u128) -> Call {
+	Call::Balances(balances::Call::Transfer(balances::Transfer {
+		dest: account_address(dest),
+		value: Compact(value),
+	}))
+}
+
+/// Value of a balances transfer call, None for other calls
+pub fn transfer_value(call: &Call) -> Option<u128> {
+	match call {
+		Call::Balances(balances::Call::Transfer(transfer)) => Some(transfer.value.0),
+		_ => None,
+	}
+}
+
+/// Era of a transaction valid for about `period` blocks from the block `current`,
+/// immortal if the period is 0
+pub fn era(period: u64, current: BlockNumber) -> Era {
+	if period == 0 {
+		return Era::Immortal;
+	}
+	let period = period
+		.checked_next_power_of_two()
+		.unwrap_or(MAX_PERIOD)
+		.max(MIN_PERIOD)
+		.min(MAX_PERIOD);
+	let phase = current % period;
+	let quantize_factor = (period >> 12).max(1);
+	Era::Mortal(period, phase / quantize_factor * quantize_factor)
+}
+
+/// Number of the block whose hash is the checkpoint of the era, the genesis block if immortal
+pub fn era_birth(era: &Era, current: BlockNumber) -> BlockNumber {
+	match era {
+		Era::Immortal => 0,
+		Era::Mortal(period, phase) => (current.max(*phase) - phase) / period * period + phase,
+	}
+}
+
+/// Number of the birth block of the period before the one born at `birth`.
+///
+/// A transaction of a mortal era signed with the checkpoint of that block has expired.
+pub fn previous_birth(era: &Era, birth: BlockNumber) -> Option<BlockNumber> {
+	match era {
+		Era::Immortal => None,
+		Era::Mortal(period, _) => birth.checked_sub(*period),
+	}
+}
+
+/// Part of a template, which may have been altered by the client
+fn decode_part<T: Decode>(field: &str, encoded: &[u8]) -> errors::Result<T> {
+	let input = &mut &encoded[..];
+	match T::decode(input) {
+		Some(part) if input.is_empty() => Ok(part),
+		_ => {
+			Err(errors::ErrorKind::InvalidExtrinsic(format!("{}: failed to decode", field)).into())
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_era() {
+		let mortal = |period, phase| Era::Mortal(period, phase).encode();
+		assert_eq!(era(0, 100).encode(), vec![0x00]);
+		assert_eq!(era(64, 123).encode(), mortal(64, 59));
+		assert_eq!(era(50, 123).encode(), mortal(64, 59));
+		assert_eq!(era(1, 123).encode(), mortal(4, 3));
+		assert_eq!(mortal(64, 59), vec![0xb5, 0x03]);
+		assert_eq!(era_birth(&era(64, 123), 123), 123);
+		assert_eq!(era_birth(&era(64, 123), 130), 123);
+		assert_eq!(era_birth(&Era::Immortal, 123), 0);
+	}
+
+	#[test]
+	fn test_assemble() {
+		let hex = |x: &str| hex::decode(x).unwrap();
+		let mut sender = [0u8; 32];
+		sender.copy_from_slice(&hex(
+			"1033e0576822a6a836f612a193036042050e286da4561f5cc5d8ee560c64dc54",
+		));
+		let mut dest = [0u8; 32];
+		dest.copy_from_slice(&hex(
+			"94d988b42d96dcbd6605ff47f19c6ab35f626eb1bc8bbd28f59a74997a253a3d",
+		));
+		let call = transfer_call(&dest, 100000000);
+		let sender = account_address(&sender);
+		let tx = UnsignedTransaction::new(&sender, 1, &Era::Mortal(64, 59), &call);
+
+		let payload = tx.signing_payload(&[0u8; 32]);
+		assert_eq!(payload.len(), 1 + 39 + 2 + 32);
+
+		let signature = hex(
+			"40b2595b4c269c29377b658abe62303d59f975d0914f205d5fe8c7f24974a56007b979bf73e76211c3c3826293def93b882ef03a0a48e479693c2cbcb0425c07",
+		);
+		let raw = tx.assemble(&signature).unwrap();
+		assert_eq!(
+			raw,
+			hex("310281ff1033e0576822a6a836f612a193036042050e286da4561f5cc5d8ee560c64dc5440b2595b4c269c29377b658abe62303d59f975d0914f205d5fe8c7f24974a56007b979bf73e76211c3c3826293def93b882ef03a0a48e479693c2cbcb0425c0704b5030400ff94d988b42d96dcbd6605ff47f19c6ab35f626eb1bc8bbd28f59a74997a253a3d0284d717")
+		);
+
+		assert!(tx.assemble(&signature[1..]).is_err());
+		let mut altered = tx.clone();
+		altered.call.0.push(0);
+		assert!(altered.assemble(&signature).is_err());
+
+		let signed: Transaction = Decode::decode(&mut &raw[..]).unwrap();
+		let (unsigned, split_signature, _) = split_signed(signed).unwrap();
+		assert_eq!(unsigned, tx);
+		assert_eq!(split_signature, signature);
+		let unsigned = Transaction {
+			signature: None,
+			call: transfer_call(&dest, 1),
+		};
+		assert!(split_signed(unsigned).is_none());
+
+		assert_eq!(transfer_value(&call), Some(100000000));
+	}
+
+	#[test]
+	fn test_verify_signature() {
+		let pair = sr25519::Pair::from_seed([7u8; 32]);
+		let public = pair.public().0;
+		let call = transfer_call(&[1u8; 32], 100);
+		let tx = UnsignedTransaction::new(&account_address(&public), 1, &era(64, 123), &call);
+
+		let payload = tx.signing_payload(&[2u8; 32]);
+		let signature = pair.sign(&payload).0.to_vec();
+		assert!(verify_signature(&payload, &signature, &public));
+
+		let mut tampered = payload.clone();
+		tampered[0] ^= 1;
+		assert!(!verify_signature(&tampered, &signature, &public));
+		let other_checkpoint = tx.signing_payload(&[3u8; 32]);
+		assert!(!verify_signature(&other_checkpoint, &signature, &public));
+		let other = sr25519::Pair::from_seed([8u8; 32]).public().0;
+		assert!(!verify_signature(&payload, &signature, &other));
+		assert!(!verify_signature(&payload, &signature[1..], &public));
+	}
+
+	#[test]
+	fn test_era_expiry() {
+		// born at 123, the periods start at 59 + 64 * n
+		let era = era(64, 123);
+		let pair = sr25519::Pair::from_seed([7u8; 32]);
+		let public = pair.public().0;
+		let call = transfer_call(&[1u8; 32], 100);
+		let tx = UnsignedTransaction::new(&account_address(&public), 1, &era, &call);
+		let checkpoint = |number: BlockNumber| [number as u8; 32];
+		let signature = pair.sign(&tx.signing_payload(&checkpoint(123))).0.to_vec();
+		let verify_at = |number| {
+			verify_signature(
+				&tx.signing_payload(&checkpoint(number)),
+				&signature,
+				&public,
+			)
+		};
+
+		// valid up to the last block of its period
+		for best in &[123, 150, 186] {
+			let birth = era_birth(&era, *best);
+			assert_eq!(birth, 123);
+			assert!(verify_at(birth));
+		}
+
+		// expired from the first block of the next period
+		for best in &[187, 200, 250] {
+			let birth = era_birth(&era, *best);
+			assert_eq!(birth, 187);
+			assert!(!verify_at(birth));
+			let previous = previous_birth(&era, birth).unwrap();
+			assert_eq!(previous, 123);
+			assert!(verify_at(previous));
+		}
+
+		// two periods later the signature matches neither birth
+		let birth = era_birth(&era, 251);
+		assert_eq!(birth, 251);
+		assert!(!verify_at(birth));
+		assert!(!verify_at(previous_birth(&era, birth).unwrap()));
+
+		assert_eq!(previous_birth(&era, 59), None);
+		assert_eq!(previous_birth(&Era::Immortal, 123), None);
+	}
+}
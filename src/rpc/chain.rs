@@ -15,37 +15,42 @@
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::{hash_map::Entry, HashMap};
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
 use std::sync::Arc;
 
 use futures::future;
 use futures::future::{Future, Loop};
+use futures::stream::{self, Stream};
 use jsonrpc_core::BoxFuture;
 use jsonrpc_derive::rpc;
 use parity_codec::Decode;
-use serde_json::Value;
-use srml_system::{EventRecord, Phase};
+use serde_json::{json, Value};
 use substrate_primitives::blake2_256;
 use yee_primitives::Address;
 use yee_primitives::AddressCodec;
-use yee_primitives::Hrp;
-use yee_runtime::Event;
 use yee_sharding_primitives::utils::shard_num_for_bytes;
 use yee_signer::tx::call::relay;
-use yee_signer::tx::types::Call;
-use yee_signer::tx::types::Transaction;
+use yee_signer::tx::types::{Call, Transaction};
 
-use crate::config::{HRP, SHARD_COUNT};
-use crate::config::Config;
+use crate::config::{Format, NetworkInfo, Networks, SharedConfig};
+use crate::rpc::address::public_key_of;
+use crate::rpc::builder::{self, UnsignedTransaction};
 use crate::rpc::client::{self, RpcClient};
+use crate::rpc::context;
+use crate::rpc::decoder;
 use crate::rpc::errors;
+use crate::rpc::metrics;
 use crate::rpc::serde::Hex;
 use crate::rpc::types::{
-	Balance, BlockNumber, get_map_storage_key, get_value_storage_key, Nonce, ResultBlock,
+	Account, Amount, amount_value, balance_value, Block, BlockNumber, format_amount,
+	get_map_storage_key, get_value_storage_key, Nonce, public_to_address, ResultBlock,
 	ResultHeader, ResultTransaction,
 };
 
+/// Upstream calls in flight per shard for a batch request
+const BATCH_CONCURRENCY: usize = 16;
+
 #[rpc]
 pub trait ChainApi {
 	#[rpc(name = "chain_getBestNumber")]
@@ -102,57 +107,361 @@ pub trait ChainApi {
 	#[rpc(name = "state_getNonce")]
 	fn get_nonce(&self, address: String, block_number: Option<BlockNumber>) -> BoxFuture<Nonce>;
 
+	#[rpc(name = "state_getNonceByHash")]
+	fn get_nonce_by_hash(&self, address: String, hash: Hex<Vec<u8>>) -> BoxFuture<Nonce>;
+
+	#[rpc(name = "state_getNonceByTimestamp")]
+	fn get_nonce_by_timestamp(&self, address: String, timestamp: u64) -> BoxFuture<Nonce>;
+
 	#[rpc(name = "state_getBalance")]
 	fn get_balance(&self, address: String, block_number: Option<BlockNumber>) -> BoxFuture<Value>;
 
+	#[rpc(name = "state_getBalanceByHash")]
+	fn get_balance_by_hash(&self, address: String, hash: Hex<Vec<u8>>) -> BoxFuture<Value>;
+
+	#[rpc(name = "state_getBalanceByTimestamp")]
+	fn get_balance_by_timestamp(&self, address: String, timestamp: u64) -> BoxFuture<Value>;
+
+	#[rpc(name = "state_getBalances")]
+	fn get_balances(
+		&self,
+		addresses: Vec<String>,
+		block_number: Option<BlockNumber>,
+	) -> BoxFuture<Vec<Value>>;
+
+	#[rpc(name = "state_getNonces")]
+	fn get_nonces(
+		&self,
+		addresses: Vec<String>,
+		block_number: Option<BlockNumber>,
+	) -> BoxFuture<Vec<Value>>;
+
+	#[rpc(name = "state_getAccount")]
+	fn get_account(
+		&self,
+		address: String,
+		block_number: Option<BlockNumber>,
+	) -> BoxFuture<Option<Value>>;
+
+	/// Validated before it is submitted if `submit.validate` is configured
 	#[rpc(name = "author_submitExtrinsic")]
 	fn submit_extrinsic(&self, raw: Hex<Vec<u8>>) -> BoxFuture<Hex<Vec<u8>>>;
+
+	/// Submit the extrinsic to the shard as is, for extrinsics without a sender
+	/// such as relay transactions
+	#[rpc(name = "author_submitExtrinsicToShard")]
+	fn submit_extrinsic_to_shard(
+		&self,
+		shard_num: u16,
+		raw: Hex<Vec<u8>>,
+	) -> BoxFuture<Hex<Vec<u8>>>;
+
+	/// Decode the extrinsic as it would be submitted, without submitting it
+	#[rpc(name = "author_decodeExtrinsic")]
+	fn decode_extrinsic(&self, raw: Hex<Vec<u8>>) -> BoxFuture<Value>;
+
+	/// Unsigned transfer with the current nonce of the sender, valid for `era` blocks
+	/// (immortal if 0)
+	#[rpc(name = "author_buildTransfer")]
+	fn build_transfer(
+		&self,
+		from: String,
+		to: String,
+		value: Amount,
+		era: Option<u64>,
+	) -> BoxFuture<Value>;
+
+	#[rpc(name = "author_assembleSigned")]
+	fn assemble_signed(
+		&self,
+		template: UnsignedTransaction,
+		signature: Hex<Vec<u8>>,
+	) -> BoxFuture<Hex<Vec<u8>>>;
 }
 
 pub struct Chain {
-	config: Config,
+	config: SharedConfig,
+	networks: Networks,
+}
+
+/// Block the state is read at
+enum At {
+	/// The best block
+	Best,
+	Number(BlockNumber),
+	Hash(Hex<Vec<u8>>),
+	/// The last block at or before the UNIX timestamp in seconds
+	Timestamp(u64),
+}
+
+impl From<Option<BlockNumber>> for At {
+	fn from(block_number: Option<BlockNumber>) -> Self {
+		match block_number {
+			Some(block_number) => At::Number(block_number),
+			None => At::Best,
+		}
+	}
+}
+
+/// Network of the current request
+struct RequestNetwork {
+	name: String,
+	info: NetworkInfo,
 	rpc_client: Arc<RpcClient>,
 }
 
 impl Chain {
 	/// Create new State API RPC handler.
-	pub fn new(config: Config) -> Self {
-		Self {
-			config: config.clone(),
-			rpc_client: Arc::new(RpcClient::new(config)),
+	pub fn new(config: SharedConfig, networks: Networks) -> Self {
+		Self { config, networks }
+	}
+
+	fn network(&self) -> errors::Result<RequestNetwork> {
+		let name = context::network();
+		let info = self
+			.networks
+			.get(&name)
+			.ok_or_else(|| errors::Error::from(errors::ErrorKind::UnknownNetwork(name.clone())))?;
+		Ok(RequestNetwork {
+			rpc_client: Arc::new(RpcClient::new(self.config.clone(), name.clone())),
+			name,
+			info,
+		})
+	}
+
+	fn format(&self) -> Format {
+		self.config.get().format
+	}
+
+	/// Network, public key and shard of the address
+	fn address_network(&self, address: &str) -> errors::Result<(RequestNetwork, [u8; 32], u16)> {
+		let (public_key, _hrp) = <[u8; 32]>::from_address(&Address(address.to_string()))
+			.map_err(|_| errors::ErrorKind::InvalidAddress(address.to_string()))?;
+
+		let network = self.network()?;
+		let shard_count = network.info.shard_count;
+
+		let shard_num = shard_num_for_bytes(&public_key, shard_count)
+			.ok_or(errors::ErrorKind::InvalidShard(None, shard_count))?;
+		check_shard_configured(&network.rpc_client, shard_num)?;
+
+		Ok((network, public_key, shard_num))
+	}
+
+	/// Read a map storage of the address on its shard
+	fn get_address_storage_future(
+		&self,
+		address: String,
+		at: At,
+		prefix: &'static [u8],
+	) -> BoxFuture<Option<Hex<Vec<u8>>>> {
+		let (network, public_key, shard_num) = match self.address_network(&address) {
+			Ok(v) => v,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+		let storage_key = Hex(get_map_storage_key(&public_key, prefix).0);
+
+		let rpc_client = network.rpc_client.clone();
+		let result = get_block_hash_at_future(rpc_client.clone(), at, shard_num);
+		let result = result.and_then(move |block_hash| {
+			client::get_storage_future(rpc_client, &storage_key, &block_hash, shard_num)
+		});
+
+		Box::new(result)
+	}
+
+	fn get_nonce_future(&self, address: String, at: At) -> BoxFuture<Nonce> {
+		let result = self.get_address_storage_future(address, at, b"System AccountNonce");
+		let result = result.and_then(|x| -> jsonrpc_core::Result<Nonce> {
+			match x {
+				Some(x) => Ok(u64_from_slice(&x.0)?),
+				None => Ok(0),
+			}
+		});
+
+		Box::new(result)
+	}
+
+	fn get_balance_future(&self, address: String, at: At) -> BoxFuture<Value> {
+		let format = self.format();
+		let result = self.get_address_storage_future(address, at, b"Balances FreeBalance");
+		let result = result.and_then(move |x| -> jsonrpc_core::Result<Value> {
+			let balance = match x {
+				Some(x) => u128_from_slice(&x.0)?,
+				None => 0,
+			};
+			Ok(amount_value(balance, format))
+		});
+
+		Box::new(result)
+	}
+
+	/// Read a map storage of each address, grouped by shard.
+	///
+	/// The results are in the order of the addresses, an address which is invalid
+	/// or whose shard failed gets an `error` instead of the value.
+	fn get_batch_future<F>(
+		&self,
+		addresses: Vec<String>,
+		block_number: Option<BlockNumber>,
+		prefix: &'static [u8],
+		decode: F,
+	) -> BoxFuture<Vec<Value>>
+	where
+		F: Fn(Option<Hex<Vec<u8>>>) -> errors::Result<(&'static str, Value)>
+			+ Send
+			+ Sync
+			+ 'static,
+	{
+		let network = match self.network() {
+			Ok(v) => v,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+		let max_addresses = self.config.get().batch.max_addresses;
+		if addresses.len() > max_addresses {
+			let e = errors::ErrorKind::TooManyAddresses(addresses.len(), max_addresses);
+			return Box::new(future::err(errors::Error::from(e).into()));
 		}
+
+		let (shards, invalid) = group_by_shard(&addresses, network.info.shard_count, prefix);
+
+		// query the shards concurrently, each at one block
+		let count = addresses.len();
+		let addresses = Arc::new(addresses);
+		let decode = Arc::new(decode);
+		let shards = shards
+			.into_iter()
+			.map(|(shard_num, keys)| {
+				let rpc_client = network.rpc_client.clone();
+				let block_hash = match block_number {
+					Some(block_number) => {
+						client::get_block_hash_future(rpc_client.clone(), block_number, shard_num)
+					}
+					None => client::get_best_hash_future(rpc_client.clone(), shard_num),
+				};
+				let indexes = keys.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+				let addresses = addresses.clone();
+				let decode = decode.clone();
+				block_hash
+					.and_then(
+						move |block_hash| -> BoxFuture<Option<(BlockNumber, Vec<_>)>> {
+							if block_hash.is_none() {
+								return Box::new(future::ok(None));
+							}
+							let header = client::get_header_future(
+								rpc_client.clone(),
+								&block_hash,
+								shard_num,
+							);
+							let values = stream::iter_ok(keys)
+								.map(move |(index, storage_key)| {
+									client::get_storage_future(
+										rpc_client.clone(),
+										&storage_key,
+										&block_hash,
+										shard_num,
+									)
+									.map(move |value| (index, value))
+								})
+								.buffered(BATCH_CONCURRENCY)
+								.collect();
+							let result = header.join(values).and_then(
+								|(header, values)| -> jsonrpc_core::Result<_> {
+									let header = header.ok_or_else(|| {
+										errors::Error::decode("header", "no header")
+									})?;
+									Ok(Some((header.number, values)))
+								},
+							);
+							Box::new(result)
+						},
+					)
+					.then(move |result| {
+						let entries = match result {
+							Ok(Some((block_number, values))) => values
+								.into_iter()
+								.map(|(index, value)| {
+									let address = &addresses[index];
+									let entry = match decode(value) {
+										Ok((name, value)) => json!({
+											"address": address,
+											"shard_num": shard_num,
+											"block_number": block_number,
+											name: value,
+										}),
+										Err(e) => batch_error(address, e.into()),
+									};
+									(index, entry)
+								})
+								.collect(),
+							// the block is not found, the entries are null
+							Ok(None) => vec![],
+							Err(e) => indexes
+								.into_iter()
+								.map(|index| (index, batch_error(&addresses[index], e.clone())))
+								.collect::<Vec<_>>(),
+						};
+						Ok::<_, jsonrpc_core::Error>(entries)
+					})
+			})
+			.collect::<Vec<_>>();
+
+		let result = future::join_all(shards).map(move |shards| {
+			let entries = shards.into_iter().flatten().chain(invalid);
+			restore_order(count, entries)
+		});
+
+		Box::new(result)
+	}
+
+	/// Network of the current request, the shard number checked
+	fn shard_network(&self, shard_num: u16) -> errors::Result<RequestNetwork> {
+		let network = self.network()?;
+		check_shard_num(shard_num, network.info.shard_count)?;
+		check_shard_configured(&network.rpc_client, shard_num)?;
+		Ok(network)
 	}
 }
 
 impl ChainApi for Chain {
 	fn get_best_number(&self, shard_num: u16) -> BoxFuture<Option<BlockNumber>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
-		let result = client::get_header_future(self.rpc_client.clone(), &None, shard_num);
+		let result = client::get_header_future(network.rpc_client.clone(), &None, shard_num);
 
-		let result = result.map(|x| x.map(|x| x.number));
+		let result = result.map(move |x| {
+			x.map(|x| {
+				metrics::set_best_number(&network.name, shard_num, x.number);
+				x.number
+			})
+		});
 
 		Box::new(result)
 	}
 
 	fn get_finalized_number(&self, shard_num: u16) -> BoxFuture<Option<BlockNumber>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
-		let result = client::get_finalized_hash_future(self.rpc_client.clone(), shard_num);
+		let result = client::get_finalized_hash_future(network.rpc_client.clone(), shard_num);
 
-		let rpc_client = self.rpc_client.clone();
+		let rpc_client = network.rpc_client.clone();
 		let result = result.and_then(move |hash| {
 			let header = client::get_header_future(rpc_client, &hash, shard_num);
 			header
 		});
 
-		let result = result.map(|x| x.map(|x| x.number));
+		let result = result.map(move |x| {
+			x.map(|x| {
+				metrics::set_finalized_number(&network.name, shard_num, x.number);
+				x.number
+			})
+		});
 
 		Box::new(result)
 	}
@@ -162,14 +471,14 @@ impl ChainApi for Chain {
 		shard_num: u16,
 		number: BlockNumber,
 	) -> BoxFuture<Option<ResultHeader>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
-		let result = client::get_block_hash_future(self.rpc_client.clone(), number, shard_num);
+		let result = client::get_block_hash_future(network.rpc_client.clone(), number, shard_num);
 
-		let rpc_client = self.rpc_client.clone();
+		let rpc_client = network.rpc_client.clone();
 		let result = result.and_then(move |hash| {
 			let header = client::get_header_future(rpc_client, &hash, shard_num);
 			header.map(|header| match (header, hash) {
@@ -190,14 +499,14 @@ impl ChainApi for Chain {
 		shard_num: u16,
 		hash: Hex<Vec<u8>>,
 	) -> BoxFuture<Option<ResultHeader>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
 		let result = future::ok(Some(hash));
 
-		let rpc_client = self.rpc_client.clone();
+		let rpc_client = network.rpc_client.clone();
 		let result = result.and_then(move |hash| {
 			let header = client::get_header_future(rpc_client, &hash, shard_num);
 			header.map(|header| match (header, hash) {
@@ -214,24 +523,31 @@ impl ChainApi for Chain {
 	}
 
 	fn get_block_by_number(&self, shard_num: u16, number: BlockNumber) -> BoxFuture<Option<Value>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
 		// get block hash
 		let get_block_hash = || -> BoxFuture<jsonrpc_core::Result<Option<Hex<Vec<u8>>>>> {
-			let result = client::get_block_hash_future(self.rpc_client.clone(), number, shard_num);
+			let result =
+				client::get_block_hash_future(network.rpc_client.clone(), number, shard_num);
 			let result = result.map(|x| Ok(x));
 			Box::new(result)
 		};
 		let result = get_block_hash();
 
-		let result = get_block_future(self.rpc_client.clone(), shard_num, false, result);
+		let result = get_block_future(
+			network.rpc_client.clone(),
+			network.info.clone(),
+			shard_num,
+			false,
+			result,
+		);
 
 		let result = get_option_value_future(result);
 
-		let result = get_value_with_address_future(result);
+		let result = get_value_with_address_future(result, network.info.clone(), self.format());
 
 		let result = result.and_then(|x| match x {
 			Ok(v) => future::ok(v),
@@ -242,18 +558,24 @@ impl ChainApi for Chain {
 	}
 
 	fn get_block_by_hash(&self, shard_num: u16, hash: Hex<Vec<u8>>) -> BoxFuture<Option<Value>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
 		let result = Box::new(future::ok(Ok(Some(hash))));
 
-		let result = get_block_future(self.rpc_client.clone(), shard_num, false, result);
+		let result = get_block_future(
+			network.rpc_client.clone(),
+			network.info.clone(),
+			shard_num,
+			false,
+			result,
+		);
 
 		let result = get_option_value_future(result);
 
-		let result = get_value_with_address_future(result);
+		let result = get_value_with_address_future(result, network.info.clone(), self.format());
 
 		let result = result.and_then(|x| match x {
 			Ok(v) => future::ok(v),
@@ -269,21 +591,27 @@ impl ChainApi for Chain {
 		block_number: BlockNumber,
 		hash: Hex<Vec<u8>>,
 	) -> BoxFuture<Option<Value>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
 		// get block hash
 		let get_block_hash = || -> BoxFuture<jsonrpc_core::Result<Option<Hex<Vec<u8>>>>> {
 			let result =
-				client::get_block_hash_future(self.rpc_client.clone(), block_number, shard_num);
+				client::get_block_hash_future(network.rpc_client.clone(), block_number, shard_num);
 			let result = result.map(|x| Ok(x));
 			Box::new(result)
 		};
 		let result = get_block_hash();
 
-		let result = get_block_future(self.rpc_client.clone(), shard_num, false, result);
+		let result = get_block_future(
+			network.rpc_client.clone(),
+			network.info.clone(),
+			shard_num,
+			false,
+			result,
+		);
 
 		// filter
 		let filter = move || -> BoxFuture<jsonrpc_core::Result<Option<ResultTransaction>>> {
@@ -311,7 +639,7 @@ impl ChainApi for Chain {
 
 		let result = get_option_value_future(result);
 
-		let result = get_value_with_address_future(result);
+		let result = get_value_with_address_future(result, network.info.clone(), self.format());
 
 		let result = result.and_then(|x| match x {
 			Ok(v) => future::ok(v),
@@ -327,21 +655,27 @@ impl ChainApi for Chain {
 		block_number: BlockNumber,
 		raw: Hex<Vec<u8>>,
 	) -> BoxFuture<Option<Value>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
 		// get block hash
 		let get_block_hash = || -> BoxFuture<jsonrpc_core::Result<Option<Hex<Vec<u8>>>>> {
 			let result =
-				client::get_block_hash_future(self.rpc_client.clone(), block_number, shard_num);
+				client::get_block_hash_future(network.rpc_client.clone(), block_number, shard_num);
 			let result = result.map(|x| Ok(x));
 			Box::new(result)
 		};
 		let result = get_block_hash();
 
-		let result = get_block_future(self.rpc_client.clone(), shard_num, true, result);
+		let result = get_block_future(
+			network.rpc_client.clone(),
+			network.info.clone(),
+			shard_num,
+			true,
+			result,
+		);
 
 		// filter
 		let filter = move || -> BoxFuture<jsonrpc_core::Result<Option<ResultTransaction>>> {
@@ -373,7 +707,7 @@ impl ChainApi for Chain {
 
 		let result = get_option_value_future(result);
 
-		let result = get_value_with_address_future(result);
+		let result = get_value_with_address_future(result, network.info.clone(), self.format());
 
 		let result = result.and_then(|x| match x {
 			Ok(v) => future::ok(v),
@@ -390,15 +724,17 @@ impl ChainApi for Chain {
 		to_block_number: BlockNumber,
 		origin_hash: Hex<Vec<u8>>,
 	) -> BoxFuture<Option<Value>> {
-		match check_shard_num(shard_num, &self.config) {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
 			Err(e) => return Box::new(future::err(e.into())),
-			_ => (),
-		}
+		};
 
-		let rpc_client = self.rpc_client.clone();
+		let rpc_client = network.rpc_client.clone();
+		let info = network.info.clone();
 		let result = future::loop_fn(from_block_number, move |block_number| {
 			get_extrinsic_by_origin_hash_future(
 				rpc_client.clone(),
+				info.clone(),
 				shard_num,
 				block_number,
 				origin_hash.clone(),
@@ -423,7 +759,7 @@ impl ChainApi for Chain {
 
 		let result = get_option_value_future(result);
 
-		let result = get_value_with_tx_decoded_future(result);
+		let result = get_value_with_tx_decoded_future(result, network.info.clone(), self.format());
 
 		let result = result.and_then(|x| match x {
 			Ok(v) => future::ok(v),
@@ -434,171 +770,144 @@ impl ChainApi for Chain {
 	}
 
 	fn get_nonce(&self, address: String, block_number: Option<BlockNumber>) -> BoxFuture<Nonce> {
-		let address = Address(address);
-
-		let (public_key, _hrp) = match <[u8; 32]>::from_address(&address)
-			.map_err(|_| errors::Error::from(errors::ErrorKind::InvalidAddress).into())
-		{
-			Ok(v) => v,
-			Err(e) => return Box::new(future::err(e)),
-		};
+		self.get_nonce_future(address, block_number.into())
+	}
 
-		let shard_count = self.config.shards.len() as u16;
+	fn get_nonce_by_hash(&self, address: String, hash: Hex<Vec<u8>>) -> BoxFuture<Nonce> {
+		self.get_nonce_future(address, At::Hash(hash))
+	}
 
-		let shard_num = match shard_num_for_bytes(&public_key, shard_count) {
-			Some(shard_num) => shard_num,
-			None => {
-				return Box::new(future::err(
-					errors::Error::from(errors::ErrorKind::InvalidShard).into(),
-				));
-			}
-		};
-		let storage_key = get_map_storage_key(&public_key, b"System AccountNonce");
-		let storage_key = Hex(storage_key.0);
+	fn get_nonce_by_timestamp(&self, address: String, timestamp: u64) -> BoxFuture<Nonce> {
+		self.get_nonce_future(address, At::Timestamp(timestamp))
+	}
 
-		// get block hash
-		let get_block_hash = || -> BoxFuture<jsonrpc_core::Result<Option<Hex<Vec<u8>>>>> {
-			match block_number {
-				Some(block_number) => {
-					let result = client::get_block_hash_future(
-						self.rpc_client.clone(),
-						block_number,
-						shard_num,
-					);
-					let result = result.map(|x| Ok(x));
-					Box::new(result)
-				}
-				None => Box::new(future::ok(Ok(None))),
-			}
-		};
-		let result = get_block_hash();
+	fn get_balance(&self, address: String, block_number: Option<BlockNumber>) -> BoxFuture<Value> {
+		self.get_balance_future(address, block_number.into())
+	}
 
-		// get nonce
-		let rpc_client = self.rpc_client.clone();
-		let get_nonce = move || -> BoxFuture<jsonrpc_core::Result<Nonce>> {
-			let result = result.and_then(move |x| match x {
-				Ok(block_hash) => {
-					let result = client::get_storage_future(
-						rpc_client,
-						&storage_key,
-						&block_hash,
-						shard_num,
-					);
-					let result = result.map(|x| -> jsonrpc_core::Result<Nonce> {
-						match x {
-							Some(x) => Ok(u64_from_slice(&x.0)?),
-							None => Ok(0),
-						}
-					});
-					Box::new(result) as BoxFuture<jsonrpc_core::Result<Nonce>>
-				}
-				Err(e) => Box::new(future::err(e)),
-			});
-			Box::new(result)
-		};
-		let result = get_nonce();
+	fn get_balance_by_hash(&self, address: String, hash: Hex<Vec<u8>>) -> BoxFuture<Value> {
+		self.get_balance_future(address, At::Hash(hash))
+	}
 
-		let result = result.and_then(|x| match x {
-			Ok(v) => future::ok(v),
-			Err(e) => future::err(e),
-		});
+	fn get_balance_by_timestamp(&self, address: String, timestamp: u64) -> BoxFuture<Value> {
+		self.get_balance_future(address, At::Timestamp(timestamp))
+	}
 
-		Box::new(result)
+	fn get_balances(
+		&self,
+		addresses: Vec<String>,
+		block_number: Option<BlockNumber>,
+	) -> BoxFuture<Vec<Value>> {
+		let format = self.format();
+		self.get_batch_future(
+			addresses,
+			block_number,
+			b"Balances FreeBalance",
+			move |value| {
+				let balance = match value {
+					Some(value) => u128_from_slice(&value.0)?,
+					None => 0,
+				};
+				Ok(("balance", amount_value(balance, format)))
+			},
+		)
 	}
 
-	fn get_balance(&self, address: String, block_number: Option<BlockNumber>) -> BoxFuture<Value> {
-		let address = Address(address);
+	fn get_nonces(
+		&self,
+		addresses: Vec<String>,
+		block_number: Option<BlockNumber>,
+	) -> BoxFuture<Vec<Value>> {
+		self.get_batch_future(addresses, block_number, b"System AccountNonce", |value| {
+			let nonce = match value {
+				Some(value) => u64_from_slice(&value.0)?,
+				None => 0,
+			};
+			Ok(("nonce", nonce.into()))
+		})
+	}
 
-		let (public_key, _hrp) = match <[u8; 32]>::from_address(&address)
-			.map_err(|_| errors::Error::from(errors::ErrorKind::InvalidAddress).into())
-		{
+	fn get_account(
+		&self,
+		address: String,
+		block_number: Option<BlockNumber>,
+	) -> BoxFuture<Option<Value>> {
+		let (network, public_key, shard_num) = match self.address_network(&address) {
 			Ok(v) => v,
-			Err(e) => return Box::new(future::err(e)),
-		};
-
-		let shard_count = self.config.shards.len() as u16;
-
-		let shard_num = match shard_num_for_bytes(&public_key, shard_count) {
-			Some(shard_num) => shard_num,
-			None => {
-				return Box::new(future::err(
-					errors::Error::from(errors::ErrorKind::InvalidShard).into(),
-				));
-			}
+			Err(e) => return Box::new(future::err(e.into())),
 		};
-		let storage_key = get_map_storage_key(&public_key, b"Balances FreeBalance");
-		let storage_key = Hex(storage_key.0);
 
-		// get block hash
-		let get_block_hash = || -> BoxFuture<jsonrpc_core::Result<Option<Hex<Vec<u8>>>>> {
-			match block_number {
-				Some(block_number) => {
-					let result = client::get_block_hash_future(
-						self.rpc_client.clone(),
-						block_number,
-						shard_num,
-					);
-					let result = result.map(|x| Ok(x));
-					Box::new(result)
-				}
-				None => Box::new(future::ok(Ok(None))),
+		// get block hash, all the fields are read at the same block
+		let rpc_client = network.rpc_client.clone();
+		let result = match block_number {
+			Some(block_number) => {
+				client::get_block_hash_future(rpc_client.clone(), block_number, shard_num)
 			}
+			None => client::get_best_hash_future(rpc_client.clone(), shard_num),
 		};
-		let result = get_block_hash();
 
-		// get balance
-		let rpc_client = self.rpc_client.clone();
-		let get_balance = move || -> BoxFuture<jsonrpc_core::Result<Balance>> {
-			let result = result.and_then(move |x| match x {
-				Ok(block_hash) => {
-					let result = client::get_storage_future(
-						rpc_client,
-						&storage_key,
-						&block_hash,
-						shard_num,
-					);
-					let result = result.map(|x| -> jsonrpc_core::Result<Balance> {
-						match x {
-							Some(x) => Ok(Balance(u128_from_slice(&x.0)?)),
-							None => Ok(Balance(0)),
-						}
-					});
-					Box::new(result) as BoxFuture<jsonrpc_core::Result<Balance>>
-				}
-				Err(e) => Box::new(future::err(e)),
-			});
+		// get account
+		let format = self.format();
+		let result = result.and_then(move |block_hash| -> BoxFuture<Option<Value>> {
+			let block_hash = match block_hash {
+				Some(block_hash) => Some(block_hash),
+				None => return Box::new(future::ok(None)),
+			};
+			let storage = |key: Vec<u8>| {
+				client::get_storage_future(rpc_client.clone(), &Hex(key), &block_hash, shard_num)
+			};
+			let map = |prefix: &[u8]| storage(get_map_storage_key(&public_key, prefix).0);
+			let storages = vec![
+				map(b"Balances FreeBalance"),
+				map(b"Balances ReservedBalance"),
+				storage(get_value_storage_key(b"Balances TotalIssuance").0),
+				map(b"System AccountNonce"),
+				map(b"Balances Locks"),
+				map(b"Balances Vesting"),
+			];
+			let header = client::get_header_future(rpc_client.clone(), &block_hash, shard_num);
+
+			let result = header.join(future::join_all(storages)).and_then(
+				move |(header, storages)| -> jsonrpc_core::Result<Option<Value>> {
+					let header =
+						header.ok_or_else(|| errors::Error::decode("header", "no header"))?;
+					let mut storages = storages.into_iter();
+					let mut next = || storages.next().and_then(|x| x);
+					let account = Account {
+						block_number: header.number,
+						free: decode_storage("free", next())?,
+						reserved: decode_storage("reserved", next())?,
+						total_issuance: decode_storage("total_issuance", next())?,
+						nonce: decode_storage("nonce", next())?,
+						locks: decode_storage("locks", next())?,
+						vesting: match next() {
+							Some(x) => Some(decode_storage("vesting", Some(x))?),
+							None => None,
+						},
+					};
+					Ok(Some(account.to_value(format)))
+				},
+			);
 			Box::new(result)
-		};
-		let result = get_balance();
-
-		let result = get_value_future(result);
-
-		let result = result.and_then(|x| match x {
-			Ok(v) => future::ok(v),
-			Err(e) => future::err(e),
 		});
 
 		Box::new(result)
 	}
 
 	fn submit_extrinsic(&self, raw: Hex<Vec<u8>>) -> BoxFuture<Hex<Vec<u8>>> {
-		let tx: Transaction = match Decode::decode(&mut &raw.0[..]) {
-			Some(v) => v,
-			None => {
-				return Box::new(future::err(
-					errors::Error::from(errors::ErrorKind::InvalidExtrinsic).into(),
-				));
-			}
+		let tx = match decode_extrinsic(&raw.0) {
+			Ok(v) => v,
+			Err(e) => return Box::new(future::err(e.into())),
 		};
 
 		let get_public = || -> errors::Result<[u8; 32]> {
-			match tx.signature {
+			match &tx.signature {
 				Some((address, _, _, _)) => {
 					let mut public = [0u8; 32];
 					(&mut public[..]).copy_from_slice(&address.0[1..]);
 					Ok(public)
 				}
-				None => Err(errors::ErrorKind::InvalidExtrinsic.into()),
+				None => Err(errors::ErrorKind::InvalidExtrinsic("not signed".to_string()).into()),
 			}
 		};
 
@@ -607,83 +916,443 @@ impl ChainApi for Chain {
 			Err(e) => return Box::new(future::err(e.into())),
 		};
 
-		let shard_count = self.config.shards.len() as u16;
+		let network = match self.network() {
+			Ok(v) => v,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+		let shard_count = network.info.shard_count;
 
 		let shard_num = match shard_num_for_bytes(&public, shard_count) {
 			Some(shard_num) => shard_num,
 			None => {
 				return Box::new(future::err(
-					errors::Error::from(errors::ErrorKind::InvalidShard).into(),
+					errors::Error::from(errors::ErrorKind::InvalidShard(None, shard_count)).into(),
 				));
 			}
 		};
 
-		let result = client::submit_extrinsic_future(self.rpc_client.clone(), &raw, shard_num);
+		let rpc_client = network.rpc_client.clone();
+		let result = if self.config.get().submit.validate {
+			validate_extrinsic_future(rpc_client.clone(), tx, public, shard_num)
+		} else {
+			Box::new(future::ok(()))
+		};
 
-		result
+		let result =
+			result.and_then(move |_| client::submit_extrinsic_future(rpc_client, &raw, shard_num));
+
+		Box::new(result)
 	}
-}
 
-fn check_shard_num(shard_num: u16, config: &Config) -> errors::Result<()> {
-	if shard_num > config.shards.len() as u16 {
-		return Err(errors::ErrorKind::InvalidShard.into());
+	fn submit_extrinsic_to_shard(
+		&self,
+		shard_num: u16,
+		raw: Hex<Vec<u8>>,
+	) -> BoxFuture<Hex<Vec<u8>>> {
+		let network = match self.shard_network(shard_num) {
+			Ok(v) => v,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+
+		client::submit_extrinsic_future(network.rpc_client.clone(), &raw, shard_num)
+	}
+
+	fn decode_extrinsic(&self, raw: Hex<Vec<u8>>) -> BoxFuture<Value> {
+		let network = match self.network() {
+			Ok(v) => v,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+		let format = self.format();
+
+		let decode = || -> errors::Result<Value> {
+			let tx = decode_extrinsic(&raw.0)?;
+			let mut tx = ResultTransaction::from_transaction(tx, &network.info);
+			tx.hash = Some(Hex(blake2_256(&raw.0).to_vec()));
+			let mut tx: Value = tx.try_into()?;
+
+			let result = extrinsic_append_address(&mut tx, &network.info);
+			set_extrinsic_error(&mut tx, result);
+			extrinsic_format_balance(&mut tx, format);
+			let result = extrinsic_append_tx_decoded(&mut tx, &network.info, format);
+			set_extrinsic_error(&mut tx, result);
+			Ok(tx)
+		};
+
+		Box::new(future::result(decode().map_err(Into::into)))
+	}
+
+	fn build_transfer(
+		&self,
+		from: String,
+		to: String,
+		value: Amount,
+		era: Option<u64>,
+	) -> BoxFuture<Value> {
+		let prepare = || -> errors::Result<_> {
+			let (network, sender, shard_num) = self.address_network(&from)?;
+			// only the shard of the sender is called, the destination shard needs no upstream
+			let dest = public_key_of(&to, &network.info)?;
+			let value = value.base_units(self.format().decimals)?;
+			Ok((
+				network,
+				sender,
+				shard_num,
+				builder::transfer_call(&dest, value),
+			))
+		};
+		let (network, sender, shard_num, call) = match prepare() {
+			Ok(v) => v,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+		let period = era.unwrap_or(builder::DEFAULT_PERIOD);
+
+		let rpc_client = network.rpc_client.clone();
+		let nonce = self.get_nonce_future(from, At::Best);
+		let header = client::get_header_future(rpc_client.clone(), &None, shard_num);
+
+		let result = nonce
+			.join(header)
+			.and_then(move |(nonce, header)| -> BoxFuture<Value> {
+				let current = match header {
+					Some(header) => header.number,
+					None => {
+						let e = errors::Error::decode("header", "no header");
+						return Box::new(future::err(e.into()));
+					}
+				};
+				let era = builder::era(period, current);
+				let birth = builder::era_birth(&era, current);
+				let sender = builder::account_address(&sender);
+				let tx = UnsignedTransaction::new(&sender, nonce, &era, &call);
+
+				let checkpoint = client::get_block_hash_future(rpc_client, birth, shard_num);
+				let result =
+					checkpoint.and_then(move |checkpoint_hash| -> jsonrpc_core::Result<Value> {
+						let checkpoint_hash = checkpoint_hash
+							.ok_or_else(|| errors::Error::decode("block_hash", "missing"))?;
+						let signing_payload = Hex(tx.signing_payload(&checkpoint_hash.0));
+						Ok(json!({
+							"shard_num": shard_num,
+							"checkpoint_hash": checkpoint_hash,
+							"signing_payload": signing_payload,
+							"template": tx,
+						}))
+					});
+				Box::new(result)
+			});
+
+		Box::new(result)
+	}
+
+	fn assemble_signed(
+		&self,
+		template: UnsignedTransaction,
+		signature: Hex<Vec<u8>>,
+	) -> BoxFuture<Hex<Vec<u8>>> {
+		let assemble = || -> errors::Result<Hex<Vec<u8>>> {
+			let raw = template.assemble(&signature.0)?;
+			decode_extrinsic(&raw)?;
+			Ok(Hex(raw))
+		};
+
+		Box::new(future::result(assemble().map_err(Into::into)))
 	}
-	Ok(())
 }
 
-fn get_block_extrinsics_result(
-	events: Option<Hex<Vec<u8>>>,
-) -> errors::Result<HashMap<u32, (bool, Vec<String>)>> {
-	let mut result = HashMap::new();
+/// Decode an extrinsic to be submitted, with the latest runtime
+fn decode_extrinsic(raw: &[u8]) -> errors::Result<Transaction> {
+	decoder::get(None)
+		.decode_transaction(raw)
+		.ok_or_else(|| errors::ErrorKind::InvalidExtrinsic("failed to decode".to_string()).into())
+}
 
-	let events = match events {
-		Some(events) => events,
-		None => return Ok(result),
+/// Check the signature and the era of a signed extrinsic at the best block of its shard,
+/// then the nonce and the transfer value against the state of the sender
+fn validate_extrinsic_future(
+	rpc_client: Arc<RpcClient>,
+	tx: Transaction,
+	public: [u8; 32],
+	shard_num: u16,
+) -> BoxFuture<()> {
+	let value = builder::transfer_value(&tx.call);
+	let (tx, signature, era) = match builder::split_signed(tx) {
+		Some(v) => v,
+		None => {
+			let e = errors::ErrorKind::InvalidExtrinsic("not signed".to_string());
+			return Box::new(future::err(errors::Error::from(e).into()));
+		}
 	};
 
-	let events: Vec<EventRecord<Event>> =
-		Decode::decode(&mut &events.0[..]).ok_or(errors::ErrorKind::ParseError)?;
+	// signature, at the birth block of the era relative to the best block
+	let best = client::get_header_future(rpc_client.clone(), &None, shard_num);
+	let tmp_rpc_client = rpc_client.clone();
+	let tmp_tx = tx.clone();
+	let result = best.and_then(move |header| -> BoxFuture<()> {
+		let best_number = match header {
+			Some(header) => header.number,
+			None => {
+				let e = errors::Error::decode("header", "no header");
+				return Box::new(future::err(e.into()));
+			}
+		};
+		let birth = builder::era_birth(&era, best_number);
+		// a mortal era signed at the birth block of the previous period has expired
+		let previous_birth = builder::previous_birth(&era, birth);
+		let verify = move |hash: Option<Hex<Vec<u8>>>| match hash {
+			Some(hash) => {
+				let payload = tmp_tx.signing_payload(&hash.0);
+				builder::verify_signature(&payload, &signature, &public)
+			}
+			None => false,
+		};
 
-	for event in events.into_iter() {
-		match event.phase {
-			Phase::ApplyExtrinsic(index) => match &event.event {
-				Event::system(system_event) => {
-					let success = match system_event {
-						srml_system::Event::ExtrinsicSuccess => true,
-						srml_system::Event::ExtrinsicFailed => false,
+		let checkpoint = client::get_block_hash_future(tmp_rpc_client.clone(), birth, shard_num);
+		let result = checkpoint.and_then(move |hash| -> BoxFuture<()> {
+			if verify(hash) {
+				return Box::new(future::ok(()));
+			}
+			let previous_birth = match previous_birth {
+				Some(previous_birth) => previous_birth,
+				None => {
+					let e = errors::Error::from(errors::ErrorKind::InvalidSignature);
+					return Box::new(future::err(e.into()));
+				}
+			};
+			let previous = client::get_block_hash_future(tmp_rpc_client, previous_birth, shard_num);
+			let result = previous.and_then(move |hash| -> jsonrpc_core::Result<()> {
+				if verify(hash) {
+					Err(errors::Error::from(errors::ErrorKind::EraExpired(best_number)).into())
+				} else {
+					Err(errors::Error::from(errors::ErrorKind::InvalidSignature).into())
+				}
+			});
+			Box::new(result)
+		});
+		Box::new(result)
+	});
+
+	// nonce and free balance of the sender
+	let nonce_key = Hex(get_map_storage_key(&public, b"System AccountNonce").0);
+	let balance_key = Hex(get_map_storage_key(&public, b"Balances FreeBalance").0);
+	let result = result.and_then(move |_| {
+		let nonce = client::get_storage_future(rpc_client.clone(), &nonce_key, &None, shard_num);
+		let balance = client::get_storage_future(rpc_client, &balance_key, &None, shard_num);
+		nonce
+			.join(balance)
+			.and_then(move |(nonce, balance)| -> jsonrpc_core::Result<()> {
+				let expected = match nonce {
+					Some(nonce) => u64_from_slice(&nonce.0)?,
+					None => 0,
+				};
+				if tx.nonce < expected {
+					let e = errors::ErrorKind::StaleNonce(tx.nonce, expected);
+					return Err(errors::Error::from(e).into());
+				}
+				if let Some(value) = value {
+					let balance = match balance {
+						Some(balance) => u128_from_slice(&balance.0)?,
+						None => 0,
 					};
-					match result.entry(index) {
-						Entry::Vacant(entry) => {
-							entry.insert((success, vec![]));
-						}
-						Entry::Occupied(mut entry) => {
-							let entry = entry.get_mut();
-							entry.0 = success;
-						}
+					if balance < value {
+						let e = errors::ErrorKind::InsufficientBalance(balance, value);
+						return Err(errors::Error::from(e).into());
 					}
 				}
-				_ => {
-					let event_str = format!("{:?}", event.event);
-					match result.entry(index) {
-						Entry::Vacant(entry) => {
-							entry.insert((false, vec![event_str]));
-						}
-						Entry::Occupied(mut entry) => {
-							let entry = entry.get_mut();
-							entry.1.push(event_str);
-						}
-					}
+				Ok(())
+			})
+	});
+
+	Box::new(result)
+}
+
+/// Hash of the block, None for the best block
+fn get_block_hash_at_future(
+	rpc_client: Arc<RpcClient>,
+	at: At,
+	shard_num: u16,
+) -> BoxFuture<Option<Hex<Vec<u8>>>> {
+	match at {
+		At::Best => Box::new(future::ok(None)),
+		At::Number(block_number) => {
+			client::get_block_hash_future(rpc_client, block_number, shard_num)
+		}
+		At::Hash(hash) => Box::new(future::ok(Some(hash))),
+		At::Timestamp(timestamp) => {
+			get_block_hash_by_timestamp_future(rpc_client, timestamp, shard_num)
+		}
+	}
+}
+
+/// Hash of the last block at or before the timestamp, by binary search of the block timestamps
+///
+/// Fails if the timestamp is before block 1, instead of reading the state of the genesis block.
+fn get_block_hash_by_timestamp_future(
+	rpc_client: Arc<RpcClient>,
+	timestamp: u64,
+	shard_num: u16,
+) -> BoxFuture<Option<Hex<Vec<u8>>>> {
+	let best = client::get_header_future(rpc_client.clone(), &None, shard_num);
+
+	let tmp_rpc_client = rpc_client.clone();
+	let result = best.and_then(move |header| {
+		let best_number = header.map(|x| x.number).unwrap_or(0);
+		search_block_by_timestamp(best_number, timestamp, move |block_number| {
+			get_timestamp_future(tmp_rpc_client.clone(), block_number, shard_num)
+		})
+	});
+
+	let result = result.and_then(move |block_number| -> BoxFuture<Option<Hex<Vec<u8>>>> {
+		match block_number {
+			Some(block_number) => {
+				client::get_block_hash_future(rpc_client, block_number, shard_num)
+			}
+			None => Box::new(future::err(
+				errors::Error::from(errors::ErrorKind::NoBlockAtTimestamp(timestamp)).into(),
+			)),
+		}
+	});
+
+	Box::new(result)
+}
+
+/// Last block up to the best block with a timestamp at or before the timestamp,
+/// `None` if the timestamp is before block 1
+fn search_block_by_timestamp<F, T>(
+	best_number: BlockNumber,
+	timestamp: u64,
+	timestamp_of: F,
+) -> BoxFuture<Option<BlockNumber>>
+where
+	F: Fn(BlockNumber) -> T + Send + 'static,
+	T: Future<Item = u64, Error = jsonrpc_core::Error> + Send + 'static,
+{
+	// block `low` is at or before the timestamp, or the genesis block;
+	// blocks after `high` are after the timestamp
+	let result = future::loop_fn(
+		(0, best_number),
+		move |(low, high)| -> BoxFuture<Loop<BlockNumber, (BlockNumber, BlockNumber)>> {
+			if low >= high {
+				return Box::new(future::ok(Loop::Break(low)));
+			}
+			let middle = low + (high - low + 1) / 2;
+			let result = timestamp_of(middle).map(move |x| {
+				if x <= timestamp {
+					Loop::Continue((middle, high))
+				} else {
+					Loop::Continue((low, middle - 1))
 				}
-			},
-			_ => {}
+			});
+			Box::new(result)
+		},
+	);
+
+	// the genesis block has no timestamp
+	Box::new(result.map(|block_number| Some(block_number).filter(|x| *x > 0)))
+}
+
+/// Timestamp of the block in seconds, 0 for the genesis block
+fn get_timestamp_future(
+	rpc_client: Arc<RpcClient>,
+	block_number: BlockNumber,
+	shard_num: u16,
+) -> BoxFuture<u64> {
+	let storage_key = Hex(get_value_storage_key(b"Timestamp Now").0);
+
+	let result = client::get_block_hash_future(rpc_client.clone(), block_number, shard_num);
+	let result = result.and_then(move |block_hash| -> BoxFuture<Option<Hex<Vec<u8>>>> {
+		match block_hash {
+			Some(block_hash) => {
+				client::get_storage_future(rpc_client, &storage_key, &Some(block_hash), shard_num)
+			}
+			None => Box::new(future::err(
+				errors::Error::decode("block_hash", format!("no block {}", block_number)).into(),
+			)),
+		}
+	});
+	let result = result.and_then(|x| -> jsonrpc_core::Result<u64> {
+		match x {
+			Some(x) => Ok(u64_from_slice(&x.0)?),
+			None => Ok(0),
 		}
+	});
+
+	Box::new(result)
+}
+
+fn check_shard_num(shard_num: u16, shard_count: u16) -> errors::Result<()> {
+	if shard_num >= shard_count {
+		return Err(errors::ErrorKind::InvalidShard(Some(shard_num), shard_count).into());
+	}
+	Ok(())
+}
+
+/// Requests are only routed to the shards with an upstream,
+/// the chain may have scaled out ahead of the conf file
+fn check_shard_configured(rpc_client: &RpcClient, shard_num: u16) -> errors::Result<()> {
+	rpc_client.get_rpc_uris(shard_num).map(|_| ())
+}
+
+/// Spec version of the runtime of a block, `None` if the node can not tell it.
+///
+/// Versions are looked up in the observed runtime ranges first. A miss past the observed
+/// blocks observes the best block too, at most once per interval, so that a range scan up to
+/// it does not ask for every block.
+fn get_spec_version_future(
+	rpc_client: Arc<RpcClient>,
+	hash: Option<Hex<Vec<u8>>>,
+	number: BlockNumber,
+	shard_num: u16,
+) -> BoxFuture<Option<u32>> {
+	let network = rpc_client.network().to_string();
+	if let Some(spec_version) = decoder::spec_version(&network, shard_num, number) {
+		return Box::new(future::ok(Some(spec_version)));
+	}
+
+	let at_block = client::get_runtime_version_future(rpc_client.clone(), &hash, shard_num)
+		.then(|x| -> jsonrpc_core::Result<Option<u32>> { Ok(x.ok().map(|x| x.spec_version)) });
+
+	if !decoder::should_observe_best(&network, shard_num, number) {
+		let result = at_block.map(move |spec_version| {
+			if let Some(spec_version) = spec_version {
+				decoder::observe_spec_version(&network, shard_num, number, spec_version);
+			}
+			spec_version
+		});
+		return Box::new(result);
 	}
 
-	Ok(result)
+	let tmp_rpc_client = rpc_client.clone();
+	let at_best = client::get_best_hash_future(rpc_client, shard_num)
+		.and_then(move |hash| -> BoxFuture<Option<(BlockNumber, u32)>> {
+			if hash.is_none() {
+				return Box::new(future::ok(None));
+			}
+			let header = client::get_header_future(tmp_rpc_client.clone(), &hash, shard_num);
+			let runtime_version =
+				client::get_runtime_version_future(tmp_rpc_client, &hash, shard_num);
+			Box::new(
+				header
+					.join(runtime_version)
+					.map(|(header, x)| header.map(|header| (header.number, x.spec_version))),
+			)
+		})
+		.then(|x| -> jsonrpc_core::Result<Option<(BlockNumber, u32)>> { Ok(x.unwrap_or(None)) });
+
+	let result = at_block.join(at_best).map(move |(spec_version, best)| {
+		if let Some(spec_version) = spec_version {
+			decoder::observe_spec_version(&network, shard_num, number, spec_version);
+		}
+		if let Some((best_number, best_spec_version)) = best {
+			decoder::observe_spec_version(&network, shard_num, best_number, best_spec_version);
+		}
+		spec_version
+	});
+	Box::new(result)
 }
 
 fn get_block_future(
 	rpc_client: Arc<RpcClient>,
+	network: NetworkInfo,
 	shard_num: u16,
 	with_raw: bool,
 	hash_future: BoxFuture<jsonrpc_core::Result<Option<Hex<Vec<u8>>>>>,
@@ -693,17 +1362,35 @@ fn get_block_future(
 	let get_block = move || -> BoxFuture<jsonrpc_core::Result<Option<ResultBlock>>> {
 		let result = hash_future.and_then(move |x| match x {
 			Ok(Some(hash)) => {
-				let result =
-					client::get_block_future(tmp_rpc_client, &Some(hash.clone()), shard_num);
-				let result = result.map(|x| -> jsonrpc_core::Result<Option<ResultBlock>> {
-					let block = match x {
-						Some(block) => block,
-						None => return Ok(None),
-					};
-					let mut block: ResultBlock = block.block.try_into()?;
-					block.header.block_hash = Some(hash);
-					Ok(Some(block))
-				});
+				let block_hash = Some(hash.clone());
+				let block =
+					client::get_block_future(tmp_rpc_client.clone(), &block_hash, shard_num);
+				// the runtime version is only asked for blocks that exist
+				let result = block
+					.and_then(move |x| -> BoxFuture<Option<(Block, Option<u32>)>> {
+						let block = match x {
+							Some(block) => block.block,
+							None => return Box::new(future::ok(None)),
+						};
+						let spec_version = get_spec_version_future(
+							tmp_rpc_client,
+							block_hash,
+							block.header.number,
+							shard_num,
+						);
+						Box::new(spec_version.map(move |spec_version| Some((block, spec_version))))
+					})
+					.map(move |x| -> jsonrpc_core::Result<Option<ResultBlock>> {
+						let (block, spec_version) = match x {
+							Some(x) => x,
+							None => return Ok(None),
+						};
+						let decoder = decoder::get(spec_version);
+						let mut block = ResultBlock::from_block(block, &network, &*decoder);
+						block.header.block_hash = Some(hash);
+						block.spec_version = spec_version;
+						Ok(Some(block))
+					});
 				Box::new(result) as BoxFuture<jsonrpc_core::Result<Option<ResultBlock>>>
 			}
 			Ok(None) => Box::new(future::ok(Ok(None))),
@@ -721,18 +1408,30 @@ fn get_block_future(
 				Ok(Some(mut block)) => {
 					let events_storage_key = get_value_storage_key(b"System Events");
 					let events_storage_key = &Hex(events_storage_key.0);
-					let block_hash = &Some(block.header.block_hash.as_ref().expect("qed").clone());
+					let block_hash = match &block.header.block_hash {
+						Some(block_hash) => Some(block_hash.clone()),
+						None => {
+							let e = errors::Error::decode("block_hash", "missing");
+							return Box::new(future::err(e.into()))
+								as BoxFuture<jsonrpc_core::Result<Option<ResultBlock>>>;
+						}
+					};
 					let events = client::get_storage_future(
 						tmp_rpc_client,
 						events_storage_key,
-						block_hash,
+						&block_hash,
 						shard_num,
 					);
 					let result =
 						events.map(move |x| -> jsonrpc_core::Result<Option<ResultBlock>> {
-							let result = get_block_extrinsics_result(x)?;
+							let result = match x {
+								Some(events) => decoder::get(block.spec_version)
+									.decode_extrinsics_result(&events.0)?,
+								None => HashMap::new(),
+							};
 							for (index, tx) in &mut block.extrinsics.iter_mut().enumerate() {
-								if !with_raw {
+								// undecodable extrinsics are always returned raw
+								if !with_raw && tx.call.is_some() {
 									tx.raw = None;
 								}
 								tx.success = result.get(&(index as u32)).as_ref().map(|x| x.0);
@@ -763,7 +1462,7 @@ where
 			Ok(Some(o)) => {
 				let result: Value = o
 					.try_into()
-					.map_err(|_| errors::Error::from(errors::ErrorKind::ParseError))?;
+					.map_err(|_| errors::Error::decode("result", "not representable as json"))?;
 				Ok(Some(result))
 			}
 			Ok(None) => Ok(None),
@@ -776,36 +1475,11 @@ where
 	Box::new(result)
 }
 
-fn get_value_future<T>(
-	future: BoxFuture<jsonrpc_core::Result<T>>,
-) -> BoxFuture<jsonrpc_core::Result<Value>>
-where
-	T: TryInto<Value> + 'static,
-{
-	// convert to value to avoid jsonrpc u128 serialize problem
-	let convert_to_value = || -> BoxFuture<jsonrpc_core::Result<Value>> {
-		let result = future.map(|x| match x {
-			Ok(o) => {
-				let result: Value = o
-					.try_into()
-					.map_err(|_| errors::Error::from(errors::ErrorKind::ParseError))?;
-				Ok(result)
-			}
-			Err(e) => Err(e),
-		});
-		Box::new(result)
-	};
-	let result = convert_to_value();
-
-	Box::new(result)
-}
-
 fn get_value_with_address_future(
 	future: BoxFuture<jsonrpc_core::Result<Option<Value>>>,
+	network: NetworkInfo,
+	format: Format,
 ) -> BoxFuture<jsonrpc_core::Result<Option<Value>>> {
-	let hrp = HRP.read().expect("qed").clone();
-	let shard_count = SHARD_COUNT.read().expect("qed").clone();
-
 	let provide_address = move || -> BoxFuture<jsonrpc_core::Result<Option<Value>>> {
 		let result = future.map(move |x| match x {
 			Ok(Some(mut value)) => {
@@ -814,7 +1488,9 @@ fn get_value_with_address_future(
 					Some(extrinsics) => match extrinsics.as_array_mut() {
 						Some(extrinsics) => {
 							for extrinsic in extrinsics {
-								extrinsic_append_address(extrinsic, hrp.clone(), shard_count)
+								let result = extrinsic_append_address(extrinsic, &network);
+								set_extrinsic_error(extrinsic, result);
+								extrinsic_format_balance(extrinsic, format);
 							}
 						}
 						None => (),
@@ -824,7 +1500,11 @@ fn get_value_with_address_future(
 
 				// process extrinsic
 				match value.get("call") {
-					Some(_) => extrinsic_append_address(&mut value, hrp.clone(), shard_count),
+					Some(_) => {
+						let result = extrinsic_append_address(&mut value, &network);
+						set_extrinsic_error(&mut value, result);
+						extrinsic_format_balance(&mut value, format);
+					}
 					None => (),
 				}
 
@@ -840,8 +1520,11 @@ fn get_value_with_address_future(
 	Box::new(result)
 }
 
-fn extrinsic_append_address(extrinsic: &mut Value, hrp: Hrp, shard_count: u16) {
-	let call = &mut extrinsic["call"];
+fn extrinsic_append_address(extrinsic: &mut Value, network: &NetworkInfo) -> errors::Result<()> {
+	let call = match extrinsic.get_mut("call") {
+		Some(call) => call,
+		None => return Ok(()),
+	};
 	let module = call.get("module").and_then(|x| x.as_u64());
 	let method = call.get("method").and_then(|x| x.as_u64());
 	match (module, method) {
@@ -849,17 +1532,41 @@ fn extrinsic_append_address(extrinsic: &mut Value, hrp: Hrp, shard_count: u16) {
 			let params = &mut call["params"];
 			if let Some(dest) = params["dest"].as_str() {
 				let dest = dest.trim_start_matches("0x");
-				match hex::decode(dest) {
-					Ok(dest) => {
-						if dest[0] == 0xFF {
-							let public = dest[1..].to_vec();
-							let shard_num = shard_num_for_bytes(&public, shard_count).expect("qed");
-							let address = public.to_address(hrp).expect("qed");
-							params["dest_address"] = Value::String(address.0);
-							params["dest_shard_num"] = Value::Number(shard_num.into());
-						}
-					}
-					Err(_) => (),
+				let dest = hex::decode(dest).map_err(|e| errors::Error::decode("dest", e))?;
+				if dest.first() == Some(&0xFF) {
+					let (address, shard_num) = public_to_address(&dest[1..], network)?;
+					params["dest_address"] = Value::String(address);
+					params["dest_shard_num"] = Value::Number(shard_num.into());
+				}
+			}
+		}
+		_ => (),
+	}
+	Ok(())
+}
+
+/// Convert the balances in the call params to the configured format,
+/// adding the formatted amount if decimals are configured.
+///
+/// Transfer values are exact decimal strings after the call is converted to json.
+fn extrinsic_format_balance(extrinsic: &mut Value, format: Format) {
+	let call = match extrinsic.get_mut("call") {
+		Some(call) => call,
+		None => return,
+	};
+	let module = call.get("module").and_then(|x| x.as_u64());
+	let method = call.get("method").and_then(|x| x.as_u64());
+	match (module, method) {
+		(Some(4), Some(0)) => {
+			let params = &mut call["params"];
+			let amount = match &params["value"] {
+				Value::String(amount) => amount.parse::<u128>().ok(),
+				amount => amount.as_u64().map(u128::from),
+			};
+			if let Some(amount) = amount {
+				params["value"] = balance_value(amount, format.balance);
+				if let Some(decimals) = format.decimals {
+					params["value_formatted"] = Value::String(format_amount(amount, decimals));
 				}
 			}
 		}
@@ -867,8 +1574,16 @@ fn extrinsic_append_address(extrinsic: &mut Value, hrp: Hrp, shard_count: u16) {
 	}
 }
 
+/// Record the error on the extrinsic instead of failing the whole result
+fn set_extrinsic_error(extrinsic: &mut Value, result: errors::Result<()>) {
+	if let Err(e) = result {
+		extrinsic["error"] = Value::String(e.to_string());
+	}
+}
+
 fn get_extrinsic_by_origin_hash_future(
 	rpc_client: Arc<RpcClient>,
+	network: NetworkInfo,
 	shard_num: u16,
 	block_number: BlockNumber,
 	origin_hash: Hex<Vec<u8>>,
@@ -881,7 +1596,7 @@ fn get_extrinsic_by_origin_hash_future(
 	};
 	let result = get_block_hash();
 
-	let result = get_block_future(rpc_client.clone(), shard_num, false, result);
+	let result = get_block_future(rpc_client.clone(), network, shard_num, false, result);
 
 	// filter
 	let filter = move || -> BoxFuture<jsonrpc_core::Result<Option<ResultTransaction>>> {
@@ -891,7 +1606,7 @@ fn get_extrinsic_by_origin_hash_future(
 					.extrinsics
 					.into_iter()
 					.filter_map(|mut tx| match &tx.call {
-						Call::Relay(call) => match call {
+						Some(Call::Relay(call)) => match call {
 							relay::Call::Transfer(transfer) => {
 								let origin_tx = &transfer.tx;
 								let origin_tx_hash = blake2_256(&origin_tx.0).to_vec();
@@ -920,16 +1635,18 @@ fn get_extrinsic_by_origin_hash_future(
 
 fn get_value_with_tx_decoded_future(
 	future: BoxFuture<jsonrpc_core::Result<Option<Value>>>,
+	network: NetworkInfo,
+	format: Format,
 ) -> BoxFuture<jsonrpc_core::Result<Option<Value>>> {
-	let hrp = HRP.read().expect("qed").clone();
-	let shard_count = SHARD_COUNT.read().expect("qed").clone();
-
 	let provide_tx_decoded = move || -> BoxFuture<jsonrpc_core::Result<Option<Value>>> {
 		let result = future.map(move |x| match x {
 			Ok(Some(mut value)) => {
 				// process extrinsic
 				match value.get("call") {
-					Some(_) => extrinsic_append_tx_decoded(&mut value, hrp.clone(), shard_count),
+					Some(_) => {
+						let result = extrinsic_append_tx_decoded(&mut value, &network, format);
+						set_extrinsic_error(&mut value, result);
+					}
 					None => (),
 				}
 
@@ -945,8 +1662,15 @@ fn get_value_with_tx_decoded_future(
 	Box::new(result)
 }
 
-fn extrinsic_append_tx_decoded(extrinsic: &mut Value, hrp: Hrp, shard_count: u16) {
-	let call = &mut extrinsic["call"];
+fn extrinsic_append_tx_decoded(
+	extrinsic: &mut Value,
+	network: &NetworkInfo,
+	format: Format,
+) -> errors::Result<()> {
+	let call = match extrinsic.get_mut("call") {
+		Some(call) => call,
+		None => return Ok(()),
+	};
 	let module = call.get("module").and_then(|x| x.as_u64());
 	let method = call.get("method").and_then(|x| x.as_u64());
 	match (module, method) {
@@ -954,26 +1678,95 @@ fn extrinsic_append_tx_decoded(extrinsic: &mut Value, hrp: Hrp, shard_count: u16
 			let params = &mut call["params"];
 			if let Some(tx) = params["tx"].as_str() {
 				let tx = tx.trim_start_matches("0x");
-				match hex::decode(tx) {
-					Ok(tx) => {
-						let tx: Transaction = Decode::decode(&mut &tx[..]).expect("qed");
-						let tx: ResultTransaction = tx.into();
-						let mut tx: Value = tx.try_into().expect("qed");
-						extrinsic_append_address(&mut tx, hrp, shard_count);
-						params["tx_decoded"] = tx;
-					}
-					Err(_) => (),
-				}
+				let tx = hex::decode(tx).map_err(|e| errors::Error::decode("tx", e))?;
+				// the origin transaction is of another shard, so its runtime version is unknown
+				let tx = decoder::get(None)
+					.decode_transaction(&tx)
+					.ok_or_else(|| errors::Error::decode("tx", "invalid transaction"))?;
+				let tx = ResultTransaction::from_transaction(tx, network);
+				let mut tx: Value = tx.try_into()?;
+				let result = extrinsic_append_address(&mut tx, network);
+				set_extrinsic_error(&mut tx, result);
+				extrinsic_format_balance(&mut tx, format);
+				params["tx_decoded"] = tx;
 			}
 		}
 		_ => (),
 	}
+	Ok(())
+}
+
+/// Entry of a batch result for an address which failed
+fn batch_error(address: &str, e: jsonrpc_core::Error) -> Value {
+	json!({ "address": address, "error": e })
+}
+
+/// Storage keys of the addresses grouped by shard, with the index of each address,
+/// and the error entries of the invalid addresses
+fn group_by_shard(
+	addresses: &[String],
+	shard_count: u16,
+	prefix: &[u8],
+) -> (
+	BTreeMap<u16, Vec<(usize, Hex<Vec<u8>>)>>,
+	Vec<(usize, Value)>,
+) {
+	let mut shards = BTreeMap::new();
+	let mut invalid = vec![];
+	for (index, address) in addresses.iter().enumerate() {
+		let public_key = match <[u8; 32]>::from_address(&Address(address.clone())) {
+			Ok((public_key, _hrp)) => public_key,
+			Err(_) => {
+				let e = errors::Error::from(errors::ErrorKind::InvalidAddress(address.clone()));
+				invalid.push((index, batch_error(address, e.into())));
+				continue;
+			}
+		};
+		let shard_num = match shard_num_for_bytes(&public_key, shard_count) {
+			Some(shard_num) => shard_num,
+			None => {
+				let e = errors::Error::from(errors::ErrorKind::InvalidShard(None, shard_count));
+				invalid.push((index, batch_error(address, e.into())));
+				continue;
+			}
+		};
+		let storage_key = Hex(get_map_storage_key(&public_key, prefix).0);
+		shards
+			.entry(shard_num)
+			.or_insert_with(Vec::new)
+			.push((index, storage_key));
+	}
+	(shards, invalid)
+}
+
+/// Entries in the order of their indexes, null for the indexes without an entry
+fn restore_order<I: IntoIterator<Item = (usize, Value)>>(count: usize, entries: I) -> Vec<Value> {
+	let mut results = vec![Value::Null; count];
+	for (index, entry) in entries {
+		results[index] = entry;
+	}
+	results
+}
+
+/// Decode a storage value, the default if the value is not stored
+fn decode_storage<T: Decode + Default>(
+	field: &str,
+	value: Option<Hex<Vec<u8>>>,
+) -> errors::Result<T> {
+	match value {
+		Some(value) => Decode::decode(&mut &value.0[..])
+			.ok_or_else(|| errors::Error::decode(field, "invalid storage value")),
+		None => Ok(T::default()),
+	}
 }
 
 fn u64_from_slice(bytes: &[u8]) -> errors::Result<u64> {
 	const LEN: usize = 8;
 	if bytes.len() != LEN {
-		return Err(errors::ErrorKind::ParseError.into());
+		return Err(errors::Error::decode(
+			"storage",
+			format!("expected {} bytes, got {}", LEN, bytes.len()),
+		));
 	}
 	let mut array = [0; LEN];
 	let bytes = &bytes[..];
@@ -984,10 +1777,155 @@ fn u64_from_slice(bytes: &[u8]) -> errors::Result<u64> {
 fn u128_from_slice(bytes: &[u8]) -> errors::Result<u128> {
 	const LEN: usize = 16;
 	if bytes.len() != LEN {
-		return Err(errors::ErrorKind::ParseError.into());
+		return Err(errors::Error::decode(
+			"storage",
+			format!("expected {} bytes, got {}", LEN, bytes.len()),
+		));
 	}
 	let mut array = [0; LEN];
 	let bytes = &bytes[..];
 	array.copy_from_slice(bytes);
 	Ok(u128::from_le_bytes(array))
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::config::{BalanceFormat, Config, Shard, DEFAULT_NETWORK};
+
+	use super::*;
+
+	#[test]
+	fn test_group_by_shard() {
+		let addresses = vec![
+			"yee1qurswpc8qurswpc8qurswpc8qurswpc8qurswpc8qurswpc8quqsw520nh",
+			"yee1invalid",
+			"yee1x6c3d0x7la4lvdfee63u4lwepw6n6m0sgwew77gaydxf9jjaaqzqzzu8dj",
+			"yee1pyysjzgfpyysjzgfpyysjzgfpyysjzgfpyysjzgfpyysjzgfpypqdmyveg",
+			"yee1qurswpc8qurswpc8qurswpc8qurswpc8qurswpc8qurswpc8quqsw520nh",
+		]
+		.into_iter()
+		.map(String::from)
+		.collect::<Vec<_>>();
+
+		let (shards, invalid) = group_by_shard(&addresses, 4, b"System AccountNonce");
+
+		let indexes = |shard_num: u16| {
+			shards[&shard_num]
+				.iter()
+				.map(|(index, _)| *index)
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(shards.keys().cloned().collect::<Vec<_>>(), vec![0, 1, 2]);
+		assert_eq!(indexes(0), vec![2]);
+		assert_eq!(indexes(1), vec![0, 4]);
+		assert_eq!(indexes(2), vec![3]);
+		assert_eq!(shards[&1][0].1 .0, shards[&1][1].1 .0);
+		assert_ne!(shards[&0][0].1 .0, shards[&2][0].1 .0);
+
+		assert_eq!(invalid.len(), 1);
+		assert_eq!(invalid[0].0, 1);
+		assert_eq!(invalid[0].1["address"], "yee1invalid");
+		assert_eq!(
+			invalid[0].1["error"]["code"],
+			errors::codes::INVALID_ADDRESS
+		);
+	}
+
+	#[test]
+	fn test_restore_order() {
+		let entries = vec![(3, json!("d")), (0, json!("a")), (1, json!("b"))];
+		assert_eq!(
+			restore_order(4, entries),
+			vec![json!("a"), json!("b"), Value::Null, json!("d")]
+		);
+		assert_eq!(restore_order(0, vec![]), Vec::<Value>::new());
+	}
+
+	#[test]
+	fn test_search_block_by_timestamp() {
+		let timestamps = vec![0, 100, 110, 120, 130];
+		let search = |best_number: BlockNumber, timestamp: u64| {
+			let timestamps = timestamps.clone();
+			search_block_by_timestamp(best_number, timestamp, move |block_number| {
+				future::ok(timestamps[block_number as usize])
+			})
+			.wait()
+			.unwrap()
+		};
+
+		// exact match
+		assert_eq!(search(4, 110), Some(2));
+		assert_eq!(search(4, 100), Some(1));
+		assert_eq!(search(4, 130), Some(4));
+		// between blocks
+		assert_eq!(search(4, 115), Some(2));
+		// before the first block
+		assert_eq!(search(4, 99), None);
+		assert_eq!(search(4, 0), None);
+		assert_eq!(search(0, 100), None);
+		// after the best block
+		assert_eq!(search(4, 500), Some(4));
+		assert_eq!(search(2, 500), Some(2));
+	}
+
+	#[test]
+	fn test_check_shard_configured() {
+		let shard = |rpc: Vec<&str>| Shard {
+			rpc: rpc.into_iter().map(|x| x.to_string()).collect(),
+		};
+		let config = Config {
+			shards: vec![
+				("0".to_string(), shard(vec!["http://127.0.0.1:9933"])),
+				("1".to_string(), shard(vec![])),
+			]
+			.into_iter()
+			.collect(),
+			..Default::default()
+		};
+		let rpc_client = RpcClient::new(SharedConfig::new(config), DEFAULT_NETWORK.to_string());
+
+		assert!(check_shard_configured(&rpc_client, 0).is_ok());
+		// a shard of a scale out not configured yet is refused
+		for shard_num in 1..3 {
+			let e: jsonrpc_core::Error = check_shard_configured(&rpc_client, shard_num)
+				.unwrap_err()
+				.into();
+			assert_eq!(
+				e.code,
+				jsonrpc_core::ErrorCode::ServerError(errors::codes::UPSTREAM_UNAVAILABLE)
+			);
+		}
+	}
+
+	#[test]
+	fn test_extrinsic_format_balance() {
+		let extrinsic = json!({
+			"call": {
+				"module": 4,
+				"method": 0,
+				"params": { "value": "18446744073709551617" },
+			}
+		});
+		let format = |balance, decimals| {
+			let mut extrinsic = extrinsic.clone();
+			extrinsic_format_balance(&mut extrinsic, Format { balance, decimals });
+			extrinsic["call"]["params"].clone()
+		};
+
+		assert_eq!(
+			format(BalanceFormat::String, None),
+			json!({ "value": "18446744073709551617" })
+		);
+		assert_eq!(
+			format(BalanceFormat::Hex, None),
+			json!({ "value": "0x10000000000000001" })
+		);
+		assert_eq!(
+			format(BalanceFormat::String, Some(8)),
+			json!({
+				"value": "18446744073709551617",
+				"value_formatted": "184467440737.09551617",
+			})
+		);
+	}
+}
@@ -16,6 +16,7 @@
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use futures::future;
 use futures::Future;
@@ -25,34 +26,60 @@ use jsonrpc_core_client::TypedClient;
 use rand::Rng;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use tokio::timer::Timeout;
 
-use crate::config::Config;
+use crate::config::SharedConfig;
+use crate::rpc::context;
 use crate::rpc::errors;
+use crate::rpc::metrics;
 use crate::rpc::serde::Hex;
-use crate::rpc::types::{BlockNumber, BlockResponse, Header};
+use crate::rpc::types::{BlockNumber, BlockResponse, Header, RuntimeVersion};
 
+/// Upstream calls not finished in time fail with an upstream timeout
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Client of the nodes of a network
 pub struct RpcClient {
-	config: Config,
+	config: SharedConfig,
+	network: String,
 }
 
 impl RpcClient {
-	pub fn new(config: Config) -> Self {
-		RpcClient { config }
+	pub fn new(config: SharedConfig, network: String) -> Self {
+		RpcClient { config, network }
 	}
 
-	fn get_random_rpc_uri(&self, shard_num: u16) -> errors::Result<String> {
-		let shard = self
-			.config
-			.shards
-			.get(&format!("{}", shard_num))
-			.ok_or(errors::Error::from(errors::ErrorKind::ConfigError))?;
+	pub fn network(&self) -> &str {
+		&self.network
+	}
+
+	pub fn get_rpc_uris(&self, shard_num: u16) -> errors::Result<Vec<String>> {
+		let config = self.config.get();
+		let shard = config
+			.shards(&self.network)
+			.and_then(|shards| shards.get(&format!("{}", shard_num)))
+			.ok_or_else(|| {
+				errors::Error::from(errors::ErrorKind::UpstreamUnavailable(
+					shard_num,
+					"shard not configured".to_string(),
+				))
+			})?;
 
 		let rpc = &shard.rpc;
 
 		if rpc.len() == 0 {
-			return Err(errors::Error::from(errors::ErrorKind::ConfigError));
+			return Err(errors::Error::from(errors::ErrorKind::UpstreamUnavailable(
+				shard_num,
+				"no rpc uri configured".to_string(),
+			)));
 		}
 
+		Ok(rpc.clone())
+	}
+
+	fn get_random_rpc_uri(&self, shard_num: u16) -> errors::Result<String> {
+		let rpc = self.get_rpc_uris(shard_num)?;
+
 		let mut rng = rand::thread_rng();
 
 		let i = rng.gen_range(0, rpc.len());
@@ -60,60 +87,83 @@ impl RpcClient {
 		Ok(rpc[i].clone())
 	}
 
-	#[allow(dead_code)]
-	pub fn call_method<T: Serialize, R: DeserializeOwned + 'static>(
+	/// Metrics label of the uri, its position in the uris of the shard
+	fn upstream_label(&self, shard_num: u16, uri: &str) -> String {
+		self.get_rpc_uris(shard_num)
+			.ok()
+			.and_then(|uris| uris.iter().position(|x| x == uri))
+			.map(|i| i.to_string())
+			.unwrap_or_else(|| "unknown".to_string())
+	}
+
+	pub fn call_method_async<
+		T: Serialize + 'static + Send,
+		R: DeserializeOwned + 'static + Send,
+	>(
 		&self,
 		method: &str,
-		returns: &'static str,
+		_returns: &'static str,
 		args: T,
 		shard_num: u16,
-	) -> errors::Result<R> {
+	) -> errors::Result<BoxFuture<R>> {
 		let uri = self.get_random_rpc_uri(shard_num)?;
 
-		let result = jsonrpc_core_client::transports::http::connect(&uri)
-			.and_then(|client: TypedClient| {
-				client
-					.call_method(method, returns, args)
-					.and_then(move |result| Ok(result))
-			})
-			.wait()
-			.map_err(|e| {
-				log::error!("RPC Client error: {:?}", e);
-				e
-			})
-			.map_err(parse_error);
-
-		result
+		Ok(self.call_uri_method_async(uri, method, args, shard_num))
 	}
 
-	pub fn call_method_async<
+	/// Call the method on the given upstream uri of the shard
+	pub fn call_uri_method_async<
 		T: Serialize + 'static + Send,
 		R: DeserializeOwned + 'static + Send,
 	>(
 		&self,
+		uri: String,
 		method: &str,
-		_returns: &'static str,
 		args: T,
 		shard_num: u16,
-	) -> errors::Result<BoxFuture<R>> {
-		let uri = self.get_random_rpc_uri(shard_num)?;
-
+	) -> BoxFuture<R> {
 		let method = method.to_owned();
 
-		let run = jsonrpc_core_client::transports::http::connect(&uri)
-			.and_then(move |client: TypedClient| {
+		context::record_upstream(shard_num, &uri);
+
+		let start = Instant::now();
+		let observed_method = method.clone();
+		let observed_upstream = self.upstream_label(shard_num, &uri);
+		let observed_network = self.network.clone();
+
+		let call = jsonrpc_core_client::transports::http::connect(&uri).and_then(
+			move |client: TypedClient| {
 				client
 					.call_method(&method, "returns", args)
 					.and_then(move |result| Ok(result))
+			},
+		);
+
+		let run = Timeout::new(call, UPSTREAM_TIMEOUT)
+			.map_err(|e| e.into_inner().unwrap_or(RpcError::Timeout))
+			.then(move |result| {
+				metrics::observe_upstream(
+					&observed_network,
+					shard_num,
+					&observed_upstream,
+					&observed_method,
+					result.is_ok(),
+					start.elapsed(),
+				);
+				result
 			})
-			.map_err(|e| {
-				log::error!("RPC Client error: {:?}", e);
+			.map_err(move |e| {
+				log::error!(
+					"RPC Client error: request_id: {}, uri: {}, {:?}",
+					context::request_id(),
+					uri,
+					e
+				);
 				e
 			})
-			.map_err(parse_error)
-			.map_err(|e| e.into());
+			.map_err(move |e| errors::Error::upstream(shard_num, e).into());
 
-		Ok(Box::new(run))
+		Box::new(run)
 	}
 }
 
@@ -128,6 +178,16 @@ pub fn get_block_hash_future(
 	Box::new(result)
 }
 
+pub fn get_best_hash_future(
+	rpc_client: Arc<RpcClient>,
+	shard_num: u16,
+) -> Box<dyn Future<Item = Option<Hex<Vec<u8>>>, Error = jsonrpc_core::Error> + Send> {
+	let result: BoxFuture<Option<Hex<Vec<u8>>>> = rpc_client
+		.call_method_async("chain_getBlockHash", "", (), shard_num)
+		.unwrap_or_else(|e| Box::new(future::err(e.into())));
+	Box::new(result)
+}
+
 pub fn get_finalized_hash_future(
 	rpc_client: Arc<RpcClient>,
 	shard_num: u16,
@@ -160,6 +220,28 @@ pub fn get_header_future(
 	Box::new(result)
 }
 
+pub fn get_runtime_version_future(
+	rpc_client: Arc<RpcClient>,
+	hash: &Option<Hex<Vec<u8>>>,
+	shard_num: u16,
+) -> Box<dyn Future<Item = RuntimeVersion, Error = jsonrpc_core::Error> + Send> {
+	let result: BoxFuture<RuntimeVersion> = match hash {
+		Some(hash) => {
+			let params = (hash.to_string(),);
+			rpc_client
+				.call_method_async("state_getRuntimeVersion", "", params, shard_num)
+				.unwrap_or_else(|e| Box::new(future::err(e.into())))
+		}
+		None => {
+			let params = ();
+			rpc_client
+				.call_method_async("state_getRuntimeVersion", "", params, shard_num)
+				.unwrap_or_else(|e| Box::new(future::err(e.into())))
+		}
+	};
+	Box::new(result)
+}
+
 pub fn get_block_future(
 	rpc_client: Arc<RpcClient>,
 	hash: &Option<Hex<Vec<u8>>>,
@@ -209,7 +291,3 @@ pub fn submit_extrinsic_future(
 		.unwrap_or_else(|e| Box::new(future::err(e.into())));
 	Box::new(result)
 }
-
-fn parse_error(error: RpcError) -> errors::Error {
-	errors::Error::from(errors::ErrorKind::RpcError(error))
-}
@@ -0,0 +1,117 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use futures::{Future, Poll};
+
+use crate::config::DEFAULT_NETWORK;
+
+thread_local! {
+	static CURRENT: RefCell<Option<Arc<RequestContext>>> = RefCell::new(None);
+}
+
+/// Context of an incoming request
+///
+/// The context is current while the handler is called and while its future is polled,
+/// so the upstream calls made on behalf of the request can be attributed to it.
+pub struct RequestContext {
+	pub id: String,
+	pub network: String,
+	upstream: Mutex<UpstreamStats>,
+}
+
+#[derive(Default, Clone)]
+pub struct UpstreamStats {
+	pub calls: usize,
+	pub shards: BTreeSet<u16>,
+	pub uris: BTreeSet<String>,
+}
+
+impl RequestContext {
+	pub fn new(network: String) -> Arc<Self> {
+		Arc::new(RequestContext {
+			id: format!("{:016x}", rand::random::<u64>()),
+			network,
+			upstream: Mutex::new(UpstreamStats::default()),
+		})
+	}
+
+	pub fn upstream(&self) -> UpstreamStats {
+		self.upstream.lock().expect("qed").clone()
+	}
+}
+
+/// Run `f` with the context as the current one
+pub fn enter<R, F: FnOnce() -> R>(context: &Arc<RequestContext>, f: F) -> R {
+	let previous = CURRENT.with(|current| current.replace(Some(context.clone())));
+	let result = f();
+	CURRENT.with(|current| current.replace(previous));
+	result
+}
+
+/// Id of the current request, `-` if none
+pub fn request_id() -> String {
+	CURRENT.with(|current| match &*current.borrow() {
+		Some(context) => context.id.clone(),
+		None => "-".to_string(),
+	})
+}
+
+/// Network of the current request, the default network if none
+pub fn network() -> String {
+	CURRENT.with(|current| match &*current.borrow() {
+		Some(context) => context.network.clone(),
+		None => DEFAULT_NETWORK.to_string(),
+	})
+}
+
+/// Record an upstream call made for the current request
+pub fn record_upstream(shard_num: u16, uri: &str) {
+	CURRENT.with(|current| {
+		if let Some(context) = &*current.borrow() {
+			let mut upstream = context.upstream.lock().expect("qed");
+			upstream.calls += 1;
+			upstream.shards.insert(shard_num);
+			upstream.uris.insert(uri.to_string());
+		}
+	})
+}
+
+/// Future polled with the context as the current one
+pub struct WithContext<F> {
+	context: Arc<RequestContext>,
+	inner: F,
+}
+
+impl<F> WithContext<F> {
+	pub fn new(context: Arc<RequestContext>, inner: F) -> Self {
+		WithContext { context, inner }
+	}
+}
+
+impl<F: Future> Future for WithContext<F> {
+	type Item = F::Item;
+	type Error = F::Error;
+
+	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+		let inner = &mut self.inner;
+		enter(&self.context, || inner.poll())
+	}
+}
@@ -0,0 +1,307 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use parity_codec::Decode;
+use srml_system::{EventRecord, Phase};
+use yee_runtime::Event;
+use yee_signer::tx::types::Transaction;
+
+use crate::rpc::errors;
+use crate::rpc::metrics;
+use crate::rpc::types::BlockNumber;
+
+/// The best block of a shard is observed at most once per interval
+const BEST_OBSERVE_INTERVAL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+	static ref REGISTRY: Registry = Registry::default();
+	static ref SPEC_VERSIONS: Mutex<HashMap<(String, u16), SpecVersions>> =
+		Mutex::new(HashMap::new());
+}
+
+/// Success and the other events of the extrinsics of a block, by extrinsic index
+pub type ExtrinsicsResult = HashMap<u32, (bool, Vec<String>)>;
+
+/// Decodes the transactions and events of the blocks of a range of runtime spec versions
+pub trait Decoder: Send + Sync {
+	fn decode_transaction(&self, raw: &[u8]) -> Option<Transaction>;
+
+	fn decode_extrinsics_result(&self, events: &[u8]) -> errors::Result<ExtrinsicsResult>;
+}
+
+/// Decoders keyed by the first spec version they apply to
+pub struct Registry {
+	decoders: BTreeMap<u32, Arc<dyn Decoder>>,
+}
+
+impl Registry {
+	pub fn register(&mut self, spec_version: u32, decoder: Arc<dyn Decoder>) {
+		self.decoders.insert(spec_version, decoder);
+	}
+
+	/// Decoder of the greatest spec version not above the given one, the latest if unknown
+	pub fn get(&self, spec_version: Option<u32>) -> Arc<dyn Decoder> {
+		let decoder = match spec_version {
+			Some(spec_version) => self.decoders.range(..=spec_version).next_back(),
+			None => self.decoders.iter().next_back(),
+		};
+		decoder
+			.or_else(|| self.decoders.iter().next())
+			.map(|(_, decoder)| decoder.clone())
+			.expect("qed")
+	}
+}
+
+impl Default for Registry {
+	/// Registry with the decoder of the compiled in runtime types.
+	///
+	/// Decoders of other runtime types are registered here as the runtime is upgraded.
+	fn default() -> Self {
+		let mut registry = Registry {
+			decoders: BTreeMap::new(),
+		};
+		registry.register(0, Arc::new(CurrentDecoder));
+		registry
+	}
+}
+
+/// Decoder of the given spec version, the latest if unknown
+pub fn get(spec_version: Option<u32>) -> Arc<dyn Decoder> {
+	REGISTRY.get(spec_version)
+}
+
+/// Spec version of the given block of a shard, if known from the observed ones
+pub fn spec_version(network: &str, shard_num: u16, number: BlockNumber) -> Option<u32> {
+	let spec_versions = SPEC_VERSIONS.lock().expect("qed");
+	let spec_version = spec_versions
+		.get(&(network.to_string(), shard_num))
+		.and_then(|x| x.get(number));
+	metrics::observe_cache("spec_version", spec_version.is_some());
+	spec_version
+}
+
+/// Whether a miss at the given block of a shard should observe the best block too.
+///
+/// Only a block past the last observed one can fall in the range ending at the best block,
+/// which is then observed at most once per `BEST_OBSERVE_INTERVAL`.
+pub fn should_observe_best(network: &str, shard_num: u16, number: BlockNumber) -> bool {
+	SPEC_VERSIONS
+		.lock()
+		.expect("qed")
+		.entry((network.to_string(), shard_num))
+		.or_insert_with(SpecVersions::default)
+		.should_observe_best(number, Instant::now())
+}
+
+/// Records the spec version of the given block of a shard
+pub fn observe_spec_version(network: &str, shard_num: u16, number: BlockNumber, spec_version: u32) {
+	SPEC_VERSIONS
+		.lock()
+		.expect("qed")
+		.entry((network.to_string(), shard_num))
+		.or_insert_with(SpecVersions::default)
+		.insert(number, spec_version);
+}
+
+/// Spec versions observed at blocks of a shard.
+///
+/// Spec versions never decrease along a chain, so a block between two blocks of the same
+/// spec version has that spec version too. Only the bounds of each runtime range are kept.
+#[derive(Default)]
+pub struct SpecVersions {
+	observed: BTreeMap<BlockNumber, u32>,
+	best_observed_at: Option<Instant>,
+}
+
+impl SpecVersions {
+	pub fn get(&self, number: BlockNumber) -> Option<u32> {
+		let (_, before) = self.observed.range(..=number).next_back()?;
+		let (_, after) = self.observed.range(number..).next()?;
+		if before == after {
+			Some(*before)
+		} else {
+			None
+		}
+	}
+
+	/// The best block is claimed as observed when due, so concurrent misses ask for it once
+	pub fn should_observe_best(&mut self, number: BlockNumber, now: Instant) -> bool {
+		let past_observed = match self.observed.keys().next_back() {
+			Some(last) => number > *last,
+			None => true,
+		};
+		let due = match self.best_observed_at {
+			Some(at) => now.duration_since(at) >= BEST_OBSERVE_INTERVAL,
+			None => true,
+		};
+		if past_observed && due {
+			self.best_observed_at = Some(now);
+		}
+		past_observed && due
+	}
+
+	pub fn insert(&mut self, number: BlockNumber, spec_version: u32) {
+		self.observed.insert(number, spec_version);
+		let observed: Vec<(BlockNumber, u32)> =
+			self.observed.iter().map(|(n, v)| (*n, *v)).collect();
+		for window in observed.windows(3) {
+			if window[0].1 == window[1].1 && window[1].1 == window[2].1 {
+				self.observed.remove(&window[1].0);
+			}
+		}
+	}
+}
+
+/// Decoder with the types of the pinned `yee-runtime` and `yee-signer`
+struct CurrentDecoder;
+
+impl Decoder for CurrentDecoder {
+	fn decode_transaction(&self, raw: &[u8]) -> Option<Transaction> {
+		Decode::decode(&mut &raw[..])
+	}
+
+	fn decode_extrinsics_result(&self, events: &[u8]) -> errors::Result<ExtrinsicsResult> {
+		let mut result = HashMap::new();
+
+		let events: Vec<EventRecord<Event>> = Decode::decode(&mut &events[..])
+			.ok_or_else(|| errors::Error::decode("events", "invalid events"))?;
+
+		for event in events.into_iter() {
+			match event.phase {
+				Phase::ApplyExtrinsic(index) => match &event.event {
+					Event::system(system_event) => {
+						let success = match system_event {
+							srml_system::Event::ExtrinsicSuccess => true,
+							srml_system::Event::ExtrinsicFailed => false,
+						};
+						match result.entry(index) {
+							Entry::Vacant(entry) => {
+								entry.insert((success, vec![]));
+							}
+							Entry::Occupied(mut entry) => {
+								let entry = entry.get_mut();
+								entry.0 = success;
+							}
+						}
+					}
+					_ => {
+						let event_str = format!("{:?}", event.event);
+						match result.entry(index) {
+							Entry::Vacant(entry) => {
+								entry.insert((false, vec![event_str]));
+							}
+							Entry::Occupied(mut entry) => {
+								let entry = entry.get_mut();
+								entry.1.push(event_str);
+							}
+						}
+					}
+				},
+				_ => {}
+			}
+		}
+
+		Ok(result)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Decoder telling which spec version it was registered at
+	struct StubDecoder(u32);
+
+	impl Decoder for StubDecoder {
+		fn decode_transaction(&self, _raw: &[u8]) -> Option<Transaction> {
+			None
+		}
+
+		fn decode_extrinsics_result(&self, _events: &[u8]) -> errors::Result<ExtrinsicsResult> {
+			let mut result = HashMap::new();
+			result.insert(self.0, (true, vec![]));
+			Ok(result)
+		}
+	}
+
+	fn registered_at(decoder: Arc<dyn Decoder>) -> u32 {
+		let result = decoder.decode_extrinsics_result(&[]).unwrap();
+		*result.keys().next().unwrap()
+	}
+
+	#[test]
+	fn test_registry() {
+		let mut registry = Registry {
+			decoders: BTreeMap::new(),
+		};
+		registry.register(5, Arc::new(StubDecoder(5)));
+		registry.register(10, Arc::new(StubDecoder(10)));
+
+		assert_eq!(registered_at(registry.get(Some(0))), 5);
+		assert_eq!(registered_at(registry.get(Some(5))), 5);
+		assert_eq!(registered_at(registry.get(Some(9))), 5);
+		assert_eq!(registered_at(registry.get(Some(10))), 10);
+		assert_eq!(registered_at(registry.get(Some(11))), 10);
+		assert_eq!(registered_at(registry.get(None)), 10);
+	}
+
+	#[test]
+	fn test_spec_versions() {
+		let mut spec_versions = SpecVersions::default();
+		assert_eq!(spec_versions.get(0), None);
+
+		spec_versions.insert(10, 1);
+		assert_eq!(spec_versions.get(10), Some(1));
+		assert_eq!(spec_versions.get(9), None);
+		assert_eq!(spec_versions.get(11), None);
+
+		spec_versions.insert(100, 1);
+		spec_versions.insert(50, 1);
+		assert_eq!(spec_versions.get(30), Some(1));
+		assert_eq!(spec_versions.get(100), Some(1));
+		assert_eq!(spec_versions.observed.len(), 2);
+
+		// an upgrade between 100 and 200
+		spec_versions.insert(200, 2);
+		assert_eq!(spec_versions.get(150), None);
+		assert_eq!(spec_versions.get(200), Some(2));
+
+		spec_versions.insert(300, 2);
+		assert_eq!(spec_versions.get(250), Some(2));
+		assert_eq!(spec_versions.get(100), Some(1));
+		assert_eq!(spec_versions.get(301), None);
+	}
+
+	#[test]
+	fn test_should_observe_best() {
+		let mut spec_versions = SpecVersions::default();
+		let now = Instant::now();
+		assert!(spec_versions.should_observe_best(10, now));
+		assert!(!spec_versions.should_observe_best(10, now));
+
+		spec_versions.insert(100, 1);
+		let later = now + BEST_OBSERVE_INTERVAL;
+		assert!(!spec_versions.should_observe_best(50, later));
+		assert!(spec_versions.should_observe_best(150, later));
+		assert!(!spec_versions.should_observe_best(150, later));
+	}
+}
@@ -18,6 +18,32 @@
 use error_chain::*;
 use jsonrpc_client_transports::RpcError;
 use log::warn;
+use serde_json::{json, Value};
+
+/// Error codes, see the error code table in docs/RPC.md
+pub mod codes {
+	pub const INTERNAL: i64 = 1000;
+	pub const NOT_IMPLEMENTED: i64 = 1001;
+	pub const INVALID_ADDRESS: i64 = 2001;
+	pub const INVALID_SHARD: i64 = 2002;
+	pub const INVALID_EXTRINSIC: i64 = 2003;
+	pub const UNKNOWN_NETWORK: i64 = 2004;
+	pub const INVALID_AMOUNT: i64 = 2005;
+	pub const INVALID_SIGNATURE: i64 = 2006;
+	pub const STALE_NONCE: i64 = 2007;
+	pub const ERA_EXPIRED: i64 = 2008;
+	pub const INSUFFICIENT_BALANCE: i64 = 2009;
+	pub const TOO_MANY_ADDRESSES: i64 = 2010;
+	pub const NO_BLOCK_AT_TIMESTAMP: i64 = 2011;
+	pub const UPSTREAM_UNAVAILABLE: i64 = 3001;
+	pub const UPSTREAM_ERROR: i64 = 3002;
+	pub const UPSTREAM_TIMEOUT: i64 = 3003;
+	pub const DECODE_FAILURE: i64 = 3004;
+	pub const NOT_READY: i64 = 3005;
+	pub const UNAUTHORIZED: i64 = 4001;
+	pub const METHOD_NOT_ALLOWED: i64 = 4002;
+	pub const RATE_LIMITED: i64 = 4003;
+}
 
 error_chain! {
 
@@ -34,33 +60,74 @@ error_chain! {
 			description("not yet implemented"),
 			display("Method Not Implemented"),
 		}
-		ConfigError {
-			description("config error"),
-			display("Config error"),
-		}
-		InvalidShard {
+		InvalidShard(shard_num: Option<u16>, shard_count: u16) {
 			description("invalid shard"),
-			display("Invalid shard"),
+			display("Invalid shard: {:?} of {}", shard_num, shard_count),
 		}
-		ParseError {
-			description("parse error"),
-			display("Parse error"),
-		}
-		InvalidAddress {
+		InvalidAddress(address: String) {
 			description("invalid address"),
-			display("Invalid address"),
+			display("Invalid address: {}", address),
 		}
 		InvalidShardCode {
 			description("invalid shard code"),
 			display("Invalid shard code"),
 		}
-		InvalidExtrinsic {
+		InvalidExtrinsic(reason: String) {
 			description("invalid extrinsic"),
-			display("Invalid extrinsic"),
+			display("Invalid extrinsic: {}", reason),
+		}
+		UnknownNetwork(network: String) {
+			description("unknown network"),
+			display("Unknown network: {}", network),
+		}
+		InvalidAmount(amount: String) {
+			description("invalid amount"),
+			display("Invalid amount: {}", amount),
+		}
+		InvalidSignature {
+			description("invalid signature"),
+			display("Invalid signature"),
+		}
+		/// The nonce of the extrinsic is below the nonce of the sender
+		StaleNonce(nonce: u64, expected: u64) {
+			description("stale nonce"),
+			display("Stale nonce: {}, expected at least {}", nonce, expected),
+		}
+		EraExpired(best_number: u64) {
+			description("era expired"),
+			display("Era expired at block {}", best_number),
+		}
+		InsufficientBalance(balance: u128, value: u128) {
+			description("insufficient balance"),
+			display("Insufficient balance: {} for {}", balance, value),
 		}
-		RpcError(e: jsonrpc_client_transports::RpcError) {
-			description("rpc error"),
-			display("Rpc error"),
+		TooManyAddresses(count: usize, max: usize) {
+			description("too many addresses"),
+			display("Too many addresses: {}, at most {}", count, max),
+		}
+		/// The timestamp is before the first block
+		NoBlockAtTimestamp(timestamp: u64) {
+			description("no block at timestamp"),
+			display("No block at or before timestamp {}", timestamp),
+		}
+		/// No upstream of the shard could be reached
+		UpstreamUnavailable(shard_num: u16, reason: String) {
+			description("upstream unavailable"),
+			display("Upstream of shard {} unavailable: {}", shard_num, reason),
+		}
+		/// The upstream returned a JSON-RPC error
+		UpstreamError(shard_num: u16, error: Value) {
+			description("upstream error"),
+			display("Upstream of shard {} error: {}", shard_num, error),
+		}
+		UpstreamTimeout(shard_num: u16) {
+			description("upstream timeout"),
+			display("Upstream of shard {} timeout", shard_num),
+		}
+		/// Data from the upstream could not be decoded
+		DecodeFailure(field: String, reason: String) {
+			description("decode failure"),
+			display("Failed to decode {}: {}", field, reason),
 		}
 		GetWorkError {
 			description("get work failed"),
@@ -70,57 +137,199 @@ error_chain! {
 			description("submit work failed"),
 			display("Submit work failed: {}", reason),
 		}
+		NotReady(health: Value) {
+			description("not ready"),
+			display("Not ready"),
+		}
+		Unauthorized {
+			description("unauthorized"),
+			display("Unauthorized"),
+		}
+		MethodNotAllowed(method: String) {
+			description("method not allowed"),
+			display("Method not allowed: {}", method),
+		}
+		RateLimited(retry_after_secs: u64) {
+			description("rate limited"),
+			display("Rate limited, retry after {}s", retry_after_secs),
+		}
+	}
+}
+
+impl Error {
+	/// Decode failure of the field
+	pub fn decode<R: ToString>(field: &str, reason: R) -> Self {
+		ErrorKind::DecodeFailure(field.to_string(), reason.to_string()).into()
+	}
+
+	/// Error from calling an upstream of the shard
+	pub fn upstream(shard_num: u16, error: RpcError) -> Self {
+		match error {
+			RpcError::JsonRpcError(e) => {
+				let e = serde_json::to_value(&e).unwrap_or(Value::Null);
+				ErrorKind::UpstreamError(shard_num, e).into()
+			}
+			RpcError::Timeout => ErrorKind::UpstreamTimeout(shard_num).into(),
+			RpcError::ParseError(_, e) => Error::decode("response", e),
+			RpcError::Other(e) => ErrorKind::UpstreamUnavailable(shard_num, e.to_string()).into(),
+		}
 	}
 }
 
 impl From<Error> for jsonrpc_core::Error {
 	fn from(e: Error) -> Self {
 		match e {
-			Error(ErrorKind::Unimplemented, _) => jsonrpc_core::Error {
-				code: jsonrpc_core::ErrorCode::ServerError(1),
-				message: "Not implemented yet".into(),
-				data: None,
-			},
-			Error(ErrorKind::ConfigError, _) => jsonrpc_core::Error {
-				code: jsonrpc_core::ErrorCode::ServerError(1),
-				message: "Internal error".into(),
-				data: None,
-			},
-			Error(ErrorKind::InvalidShard, _) => jsonrpc_core::Error {
-				code: jsonrpc_core::ErrorCode::ServerError(1),
-				message: "Invalid shard".into(),
-				data: None,
-			},
-			Error(ErrorKind::ParseError, _) => jsonrpc_core::Error {
-				code: jsonrpc_core::ErrorCode::ServerError(1),
-				message: "Parse error".into(),
-				data: None,
-			},
-			Error(ErrorKind::InvalidAddress, _) => jsonrpc_core::Error {
-				code: jsonrpc_core::ErrorCode::ServerError(1),
-				message: "Invalid address".into(),
-				data: None,
-			},
-			Error(ErrorKind::RpcError(e), _) => match e {
-				RpcError::JsonRpcError(e) => {
-					serde_json::from_str(&serde_json::to_string(&e).unwrap()).unwrap()
-				}
-				other => jsonrpc_core::Error {
-					code: jsonrpc_core::ErrorCode::ServerError(1),
-					message: jsonrpc_core::ErrorCode::ServerError(1).description(),
-					data: Some(format!("{:?}", other).into()),
-				},
-			},
+			Error(ErrorKind::Unimplemented, _) => {
+				error(codes::NOT_IMPLEMENTED, "Not implemented yet", None)
+			}
+			Error(ErrorKind::InvalidShard(shard_num, shard_count), _) => error(
+				codes::INVALID_SHARD,
+				"Invalid shard",
+				Some(json!({ "shard_num": shard_num, "shard_count": shard_count })),
+			),
+			Error(ErrorKind::InvalidAddress(address), _) => error(
+				codes::INVALID_ADDRESS,
+				"Invalid address",
+				Some(json!({ "address": address })),
+			),
+			Error(ErrorKind::InvalidShardCode, _) => {
+				error(codes::INVALID_SHARD, "Invalid shard code", None)
+			}
+			Error(ErrorKind::InvalidExtrinsic(reason), _) => error(
+				codes::INVALID_EXTRINSIC,
+				"Invalid extrinsic",
+				Some(json!({ "reason": reason })),
+			),
+			Error(ErrorKind::UnknownNetwork(network), _) => error(
+				codes::UNKNOWN_NETWORK,
+				"Unknown network",
+				Some(json!({ "network": network })),
+			),
+			Error(ErrorKind::InvalidAmount(amount), _) => error(
+				codes::INVALID_AMOUNT,
+				"Invalid amount",
+				Some(json!({ "amount": amount })),
+			),
+			Error(ErrorKind::InvalidSignature, _) => {
+				error(codes::INVALID_SIGNATURE, "Invalid signature", None)
+			}
+			Error(ErrorKind::StaleNonce(nonce, expected), _) => error(
+				codes::STALE_NONCE,
+				"Stale nonce",
+				Some(json!({ "nonce": nonce, "expected": expected })),
+			),
+			Error(ErrorKind::EraExpired(best_number), _) => error(
+				codes::ERA_EXPIRED,
+				"Era expired",
+				Some(json!({ "best_number": best_number })),
+			),
+			// u128 as strings, as the balance format is not known here
+			Error(ErrorKind::InsufficientBalance(balance, value), _) => error(
+				codes::INSUFFICIENT_BALANCE,
+				"Insufficient balance",
+				Some(json!({ "balance": balance.to_string(), "value": value.to_string() })),
+			),
+			Error(ErrorKind::TooManyAddresses(count, max), _) => error(
+				codes::TOO_MANY_ADDRESSES,
+				"Too many addresses",
+				Some(json!({ "count": count, "max": max })),
+			),
+			Error(ErrorKind::NoBlockAtTimestamp(timestamp), _) => error(
+				codes::NO_BLOCK_AT_TIMESTAMP,
+				"No block at timestamp",
+				Some(json!({ "timestamp": timestamp })),
+			),
+			Error(ErrorKind::UpstreamUnavailable(shard_num, reason), _) => error(
+				codes::UPSTREAM_UNAVAILABLE,
+				"Upstream unavailable",
+				Some(json!({ "shard_num": shard_num, "reason": reason })),
+			),
+			Error(ErrorKind::UpstreamError(shard_num, upstream_error), _) => error(
+				codes::UPSTREAM_ERROR,
+				"Upstream error",
+				Some(json!({ "shard_num": shard_num, "error": upstream_error })),
+			),
+			Error(ErrorKind::UpstreamTimeout(shard_num), _) => error(
+				codes::UPSTREAM_TIMEOUT,
+				"Upstream timeout",
+				Some(json!({ "shard_num": shard_num })),
+			),
+			Error(ErrorKind::DecodeFailure(field, reason), _) => error(
+				codes::DECODE_FAILURE,
+				"Decode failure",
+				Some(json!({ "field": field, "reason": reason })),
+			),
+			Error(ErrorKind::NotReady(health), _) => {
+				error(codes::NOT_READY, "Not ready", Some(health))
+			}
+			Error(ErrorKind::Unauthorized, _) => error(codes::UNAUTHORIZED, "Unauthorized", None),
+			Error(ErrorKind::MethodNotAllowed(method), _) => error(
+				codes::METHOD_NOT_ALLOWED,
+				"Method not allowed",
+				Some(json!({ "method": method })),
+			),
+			Error(ErrorKind::RateLimited(retry_after_secs), _) => error(
+				codes::RATE_LIMITED,
+				"Rate limited",
+				Some(json!({ "retry_after_secs": retry_after_secs })),
+			),
 			e => internal(e),
 		}
 	}
 }
 
+fn error(code: i64, message: &str, data: Option<Value>) -> jsonrpc_core::Error {
+	jsonrpc_core::Error {
+		code: jsonrpc_core::ErrorCode::ServerError(code),
+		message: message.into(),
+		data,
+	}
+}
+
+/// Internal errors are logged, the details are not returned to the client
 pub fn internal<E: ::std::fmt::Debug>(e: E) -> jsonrpc_core::Error {
 	warn!("Unknown error: {:?}", e);
-	jsonrpc_core::Error {
-		code: jsonrpc_core::ErrorCode::InternalError,
-		message: jsonrpc_core::ErrorCode::InternalError.description(),
-		data: Some(format!("{:?}", e).into()),
+	error(codes::INTERNAL, "Internal error", None)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_error_codes() {
+		let e: jsonrpc_core::Error = Error::from(ErrorKind::InvalidShard(Some(9), 4)).into();
+		assert_eq!(
+			e.code,
+			jsonrpc_core::ErrorCode::ServerError(codes::INVALID_SHARD)
+		);
+		assert_eq!(e.data, Some(json!({"shard_num": 9, "shard_count": 4})));
+
+		let e: jsonrpc_core::Error = Error::upstream(1, RpcError::Timeout).into();
+		assert_eq!(
+			e.code,
+			jsonrpc_core::ErrorCode::ServerError(codes::UPSTREAM_TIMEOUT)
+		);
+		assert_eq!(e.data, Some(json!({"shard_num": 1})));
+
+		let e: jsonrpc_core::Error = Error::from(ErrorKind::RateLimited(10)).into();
+		assert_eq!(
+			e.code,
+			jsonrpc_core::ErrorCode::ServerError(codes::RATE_LIMITED)
+		);
+		assert_eq!(e.data, Some(json!({"retry_after_secs": 10})));
+
+		let e: jsonrpc_core::Error = Error::decode("events", "invalid events").into();
+		assert_eq!(
+			e.code,
+			jsonrpc_core::ErrorCode::ServerError(codes::DECODE_FAILURE)
+		);
+
+		let e: jsonrpc_core::Error = Error::from(ErrorKind::GetWorkError).into();
+		assert_eq!(
+			e.code,
+			jsonrpc_core::ErrorCode::ServerError(codes::INTERNAL)
+		);
+		assert_eq!(e.data, None);
 	}
 }
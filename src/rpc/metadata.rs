@@ -15,11 +15,14 @@
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::fmt;
 use std::sync::Arc;
 
 use futures::sync::mpsc;
 use pubsub::{PubSubMetadata, Session};
 
+use crate::config::DEFAULT_NETWORK;
+
 /// RPC Metadata.
 ///
 /// Manages persistent session for transports that support it
@@ -28,6 +31,25 @@ use pubsub::{PubSubMetadata, Session};
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	identity: Option<String>,
+	transport: Option<Transport>,
+	client_ip: Option<String>,
+	network: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transport {
+	Http,
+	Ws,
+}
+
+impl fmt::Display for Transport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Transport::Http => write!(f, "http"),
+			Transport::Ws => write!(f, "ws"),
+		}
+	}
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
@@ -43,9 +65,61 @@ impl Metadata {
 	pub fn new(transport: mpsc::Sender<String>) -> Self {
 		Metadata {
 			session: Some(Arc::new(Session::new(transport))),
+			identity: None,
+			transport: Some(Transport::Ws),
+			client_ip: None,
+			network: None,
 		}
 	}
 
+	/// Create new `Metadata` for HTTP requests.
+	pub fn http() -> Self {
+		Metadata {
+			transport: Some(Transport::Http),
+			..Default::default()
+		}
+	}
+
+	/// Set the client IP reported by the transport.
+	pub fn with_client_ip(mut self, client_ip: Option<String>) -> Self {
+		self.client_ip = client_ip;
+		self
+	}
+
+	/// Set the network requested by the URL path or the `network` query parameter.
+	pub fn with_network(mut self, network: Option<String>) -> Self {
+		self.network = network;
+		self
+	}
+
+	/// Set the identity authenticated by the transport.
+	pub fn with_identity(mut self, identity: Option<String>) -> Self {
+		self.identity = identity;
+		self
+	}
+
+	/// Identity of the API key used by the client, `None` if anonymous.
+	pub fn identity(&self) -> Option<&str> {
+		self.identity.as_ref().map(AsRef::as_ref)
+	}
+
+	pub fn transport(&self) -> Option<Transport> {
+		self.transport
+	}
+
+	/// Client IP from the `X-Forwarded-For` or `X-Real-IP` header set by a trusted proxy.
+	pub fn client_ip(&self) -> Option<&str> {
+		self.client_ip.as_ref().map(AsRef::as_ref)
+	}
+
+	/// Network of the request, the default network if not requested.
+	pub fn network(&self) -> &str {
+		self.network
+			.as_ref()
+			.map(AsRef::as_ref)
+			.unwrap_or(DEFAULT_NETWORK)
+	}
+
 	/// Create new `Metadata` for tests.
 	#[cfg(test)]
 	pub fn new_test() -> (mpsc::Receiver<String>, Self) {
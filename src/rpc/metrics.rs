@@ -0,0 +1,175 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::warn;
+use prometheus::{
+	Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, TextEncoder,
+};
+
+use crate::rpc::types::BlockNumber;
+
+lazy_static! {
+	static ref RPC_REQUESTS: IntCounterVec = counter_vec(
+		"panel_rpc_requests_total",
+		"Panel RPC requests",
+		&["method", "status"]
+	);
+	static ref RPC_DURATION: HistogramVec = histogram_vec(
+		"panel_rpc_request_duration_seconds",
+		"Panel RPC request latency",
+		&["method"]
+	);
+	static ref UPSTREAM_REQUESTS: IntCounterVec = counter_vec(
+		"panel_upstream_requests_total",
+		"Upstream node RPC calls",
+		&["network", "shard", "upstream", "method"]
+	);
+	static ref UPSTREAM_ERRORS: IntCounterVec = counter_vec(
+		"panel_upstream_errors_total",
+		"Upstream node RPC call errors",
+		&["network", "shard", "upstream", "method"]
+	);
+	static ref UPSTREAM_DURATION: HistogramVec = histogram_vec(
+		"panel_upstream_request_duration_seconds",
+		"Upstream node RPC call latency",
+		&["network", "shard", "upstream"]
+	);
+	static ref CACHE_REQUESTS: IntCounterVec = counter_vec(
+		"panel_cache_requests_total",
+		"Cache lookups",
+		&["cache", "result"]
+	);
+	static ref BEST_NUMBER: IntGaugeVec = gauge_vec(
+		"panel_best_number",
+		"Best block number seen by the panel",
+		&["network", "shard"]
+	);
+	static ref FINALIZED_NUMBER: IntGaugeVec = gauge_vec(
+		"panel_finalized_number",
+		"Finalized block number seen by the panel",
+		&["network", "shard"]
+	);
+	static ref WS_SESSIONS: IntGauge = gauge("panel_ws_sessions", "Open WS sessions");
+}
+
+/// Record a panel RPC call.
+///
+/// Unknown methods are recorded as `unknown` to bound the label values.
+pub fn observe_call(method: &str, known: bool, success: bool, duration: Duration) {
+	let method = if known { method } else { "unknown" };
+	let status = if success { "ok" } else { "error" };
+	RPC_REQUESTS.with_label_values(&[method, status]).inc();
+	RPC_DURATION
+		.with_label_values(&[method])
+		.observe(seconds(duration));
+}
+
+/// Record an upstream node RPC call.
+///
+/// The upstream is labeled by its position in the configured uris of the shard,
+/// the uris themselves may contain credentials.
+pub fn observe_upstream(
+	network: &str,
+	shard_num: u16,
+	upstream: &str,
+	method: &str,
+	success: bool,
+	duration: Duration,
+) {
+	let shard = shard_num.to_string();
+	UPSTREAM_REQUESTS
+		.with_label_values(&[network, &shard, upstream, method])
+		.inc();
+	if !success {
+		UPSTREAM_ERRORS
+			.with_label_values(&[network, &shard, upstream, method])
+			.inc();
+	}
+	UPSTREAM_DURATION
+		.with_label_values(&[network, &shard, upstream])
+		.observe(seconds(duration));
+}
+
+/// Record a cache lookup, the hit ratio is `hit` over all lookups of the cache
+pub fn observe_cache(cache: &str, hit: bool) {
+	let result = if hit { "hit" } else { "miss" };
+	CACHE_REQUESTS.with_label_values(&[cache, result]).inc();
+}
+
+pub fn set_best_number(network: &str, shard_num: u16, number: BlockNumber) {
+	BEST_NUMBER
+		.with_label_values(&[network, &shard_num.to_string()])
+		.set(number as i64);
+}
+
+pub fn set_finalized_number(network: &str, shard_num: u16, number: BlockNumber) {
+	FINALIZED_NUMBER
+		.with_label_values(&[network, &shard_num.to_string()])
+		.set(number as i64);
+}
+
+pub fn ws_session_opened() {
+	WS_SESSIONS.inc();
+}
+
+pub fn ws_session_closed() {
+	WS_SESSIONS.dec();
+}
+
+/// Encode all metrics in the prometheus text format
+pub fn encode() -> (String, String) {
+	let encoder = TextEncoder::new();
+	let mut buffer = vec![];
+	if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+		warn!("Failed to encode metrics: {:?}", e);
+	}
+	(
+		encoder.format_type().to_string(),
+		String::from_utf8_lossy(&buffer).into_owned(),
+	)
+}
+
+fn seconds(duration: Duration) -> f64 {
+	duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9
+}
+
+fn counter_vec(name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+	let metric = IntCounterVec::new(Opts::new(name, help), labels).expect("qed");
+	prometheus::register(Box::new(metric.clone())).expect("qed");
+	metric
+}
+
+fn histogram_vec(name: &str, help: &str, labels: &[&str]) -> HistogramVec {
+	let metric = HistogramVec::new(HistogramOpts::new(name, help), labels).expect("qed");
+	prometheus::register(Box::new(metric.clone())).expect("qed");
+	metric
+}
+
+fn gauge_vec(name: &str, help: &str, labels: &[&str]) -> IntGaugeVec {
+	let metric = IntGaugeVec::new(Opts::new(name, help), labels).expect("qed");
+	prometheus::register(Box::new(metric.clone())).expect("qed");
+	metric
+}
+
+fn gauge(name: &str, help: &str) -> IntGauge {
+	let metric = IntGauge::new(name, help).expect("qed");
+	prometheus::register(Box::new(metric.clone())).expect("qed");
+	metric
+}
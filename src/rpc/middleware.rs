@@ -0,0 +1,132 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::Instant;
+
+use futures::future::{self, Either, Future};
+use jsonrpc_core::{Call, ErrorCode, Output};
+
+use crate::config::SharedConfig;
+use crate::rpc::access_log::{self, AccessLogEntry, AccessLogger};
+use crate::rpc::auth::Authenticator;
+use crate::rpc::context::{self, RequestContext, WithContext};
+use crate::rpc::errors;
+use crate::rpc::metadata::Metadata;
+use crate::rpc::metrics;
+
+pub type CallFuture = Box<dyn Future<Item = Option<Output>, Error = ()> + Send>;
+
+/// RPC middleware applied to every call before it reaches the handlers.
+///
+/// Checks the method allow-list, records metrics and writes the access log.
+pub struct RpcMiddleware {
+	authenticator: Authenticator,
+	access_logger: AccessLogger,
+}
+
+impl RpcMiddleware {
+	pub fn new(config: SharedConfig) -> Self {
+		RpcMiddleware {
+			authenticator: Authenticator::new(config.clone()),
+			access_logger: AccessLogger::new(config),
+		}
+	}
+}
+
+impl jsonrpc_core::Middleware<Metadata> for RpcMiddleware {
+	type Future = jsonrpc_core::middleware::NoopFuture;
+	type CallFuture = CallFuture;
+
+	fn on_call<F, X>(&self, call: Call, meta: Metadata, next: F) -> Either<Self::CallFuture, X>
+	where
+		F: FnOnce(Call, Metadata) -> X + Send,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		let (method, params) = match &call {
+			Call::MethodCall(method_call) => (method_call.method.clone(), &method_call.params),
+			Call::Notification(notification) => (notification.method.clone(), &notification.params),
+			_ => return Either::B(next(call, meta)),
+		};
+
+		let request_context = RequestContext::new(meta.network().to_string());
+
+		let access_logger = self.access_logger.clone();
+		let mut entry = AccessLogEntry {
+			request_id: request_context.id.clone(),
+			client_ip: meta.client_ip().map(ToString::to_string),
+			transport: meta.transport().map(|x| x.to_string()),
+			identity: meta.identity().map(ToString::to_string),
+			network: request_context.network.clone(),
+			method: method.clone(),
+			params: if access_logger.enabled() {
+				access_log::summarize_params(params)
+			} else {
+				String::new()
+			},
+			shards: vec![],
+			upstream_uris: vec![],
+			upstream_calls: 0,
+			latency_ms: 0,
+			error_code: None,
+		};
+
+		let start = Instant::now();
+
+		let result: CallFuture = if self.authenticator.is_allowed(meta.identity(), &method) {
+			Box::new(context::enter(&request_context, || next(call, meta)))
+		} else {
+			let output = match &call {
+				Call::MethodCall(method_call) => {
+					let error =
+						errors::Error::from(errors::ErrorKind::MethodNotAllowed(method.clone()));
+					Some(Output::from(
+						Err(error.into()),
+						method_call.id.clone(),
+						method_call.jsonrpc,
+					))
+				}
+				_ => None,
+			};
+			Box::new(future::ok(output))
+		};
+
+		let result = WithContext::new(request_context.clone(), result).map(move |output| {
+			let elapsed = start.elapsed();
+
+			let (known, error_code) = match &output {
+				Some(Output::Failure(failure)) => (
+					failure.error.code != ErrorCode::MethodNotFound,
+					Some(failure.error.code.code()),
+				),
+				_ => (true, None),
+			};
+			metrics::observe_call(&method, known, error_code.is_none(), elapsed);
+
+			let upstream = request_context.upstream();
+			entry.shards = upstream.shards.into_iter().collect();
+			entry.upstream_uris = upstream.uris.into_iter().collect();
+			entry.upstream_calls = upstream.calls;
+			entry.latency_ms = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+			entry.error_code = error_code;
+			access_logger.log(&entry);
+
+			output
+		});
+
+		Either::A(Box::new(result))
+	}
+}
@@ -62,6 +62,37 @@ pub trait SerdeHex: Sized {
 	}
 }
 
+impl SerdeHex for u128 {
+	const DEFAULT_UINT_SIZE: usize = 16;
+
+	type Error = io::Error;
+
+	fn uint_size() -> Option<usize> {
+		Some(Self::DEFAULT_UINT_SIZE)
+	}
+
+	fn into_bytes(&self) -> Result<Vec<u8>, Self::Error> {
+		Ok(self.to_be_bytes().to_vec())
+	}
+
+	fn from_bytes(src: &[u8]) -> Result<Self, Self::Error> {
+		let mut bytes = [0u8; Self::DEFAULT_UINT_SIZE];
+
+		let len = src.len();
+		if len > Self::DEFAULT_UINT_SIZE {
+			return Err(io::ErrorKind::InvalidInput.into());
+		}
+		for i in 0..len {
+			let j = Self::DEFAULT_UINT_SIZE - i - 1;
+			bytes[j] = src[len - i - 1];
+		}
+
+		let u = u128::from_be_bytes(bytes);
+
+		Ok(u)
+	}
+}
+
 impl SerdeHex for u64 {
 	const DEFAULT_UINT_SIZE: usize = 8;
 
@@ -342,4 +373,50 @@ mod tests {
 		.unwrap();
 		assert_eq!(s, r#"{"a":"x","b":"0x64"}"#.to_string());
 	}
+
+	#[test]
+	fn test_de_u128() {
+		#[derive(Debug, Deserialize, PartialEq)]
+		struct A {
+			a: String,
+			#[serde(with = "SerdeHex")]
+			b: u128,
+		}
+
+		let s: A = serde_json::from_str(r#"{"a":"x", "b":"0x1ffffffffffffffff"}"#).unwrap();
+		assert_eq!(
+			s,
+			A {
+				a: "x".to_string(),
+				b: 36893488147419103231,
+			}
+		);
+	}
+
+	#[test]
+	fn test_ser_u128() {
+		#[derive(Debug, Serialize, PartialEq)]
+		struct A {
+			a: String,
+			#[serde(with = "SerdeHex")]
+			b: u128,
+		}
+
+		let s = serde_json::to_string(&A {
+			a: "x".to_string(),
+			b: 0,
+		})
+		.unwrap();
+		assert_eq!(s, r#"{"a":"x","b":"0x0"}"#.to_string());
+
+		let s = serde_json::to_string(&A {
+			a: "x".to_string(),
+			b: u128::max_value(),
+		})
+		.unwrap();
+		assert_eq!(
+			s,
+			r#"{"a":"x","b":"0xffffffffffffffffffffffffffffffff"}"#.to_string()
+		);
+	}
 }
@@ -15,30 +15,184 @@
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
-use log::error;
+use std::cell::RefCell;
 use std::io;
+use std::net::IpAddr;
+
+use log::error;
+use pubsub::PubSubMetadata;
+
+use crate::config::{Networks, Server, SharedConfig};
+use crate::rpc::auth::{self, Authenticator};
+use crate::rpc::metrics;
+use crate::rpc::middleware::RpcMiddleware;
 
 const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
 
+const HEALTH_PATH: &str = "/health";
+
+const METRICS_PATH: &str = "/metrics";
+
+/// Method name matched against the `[auth]` patterns to allow `/metrics`
+const METRICS_METHOD: &str = "metrics";
+
 type Metadata = crate::rpc::metadata::Metadata;
-type RpcHandler = pubsub::PubSubHandler<Metadata>;
+type RpcHandler = pubsub::PubSubHandler<Metadata, RpcMiddleware>;
 
-pub fn start_http(addr: &std::net::SocketAddr, io: RpcHandler) -> io::Result<http::Server> {
-	http::ServerBuilder::new(io)
-		.threads(4)
-		.health_api(("/health", "system_health"))
-		.rest_api(http::RestApi::Unsecure)
-		.cors(http::DomainsValidation::Disabled)
-		.max_request_body_size(MAX_PAYLOAD)
-		.start_http(addr)
+thread_local! {
+	/// Connection info resolved by the WS request middleware.
+	///
+	/// This relies on the WS server handling a handshake on one thread: the middleware runs
+	/// first, then the metadata is extracted right after, before any other handshake.
+	/// A handshake refused after the middleware, by the origin or host checks, never gets
+	/// its metadata extracted, so the middleware clears the info left by such a handshake.
+	static WS_CONNECTION: RefCell<Option<WsConnection>> = RefCell::new(None);
+}
+
+struct WsConnection {
+	identity: Option<String>,
+	client_ip: Option<String>,
+	network: Option<String>,
+}
+
+pub fn start_http(
+	addr: &std::net::SocketAddr,
+	server: &Server,
+	authenticator: Authenticator,
+	networks: Networks,
+	config: SharedConfig,
+	io: RpcHandler,
+) -> io::Result<http::Server> {
+	let extractor_authenticator = authenticator.clone();
+	http::ServerBuilder::with_meta_extractor(
+		io,
+		move |request: &http::hyper::Request<http::hyper::Body>| {
+			let identity = extractor_authenticator
+				.identify(http_token(request))
+				.unwrap_or(None);
+			let header = |name| {
+				request
+					.headers()
+					.get(name)
+					.and_then(|value| value.to_str().ok())
+			};
+			let client_ip = forwarded_ip(
+				header("x-forwarded-for"),
+				header("x-real-ip"),
+				&trusted_proxies(&config),
+			);
+			let resource = request
+				.uri()
+				.path_and_query()
+				.map(|x| x.as_str())
+				.unwrap_or("/");
+			let network = if request.uri().path() == HEALTH_PATH {
+				auth::query_param(resource, "network").map(ToString::to_string)
+			} else {
+				request_network(resource, |x| networks.get(x).is_some())
+			};
+			Metadata::http()
+				.with_identity(identity)
+				.with_client_ip(client_ip)
+				.with_network(network)
+		},
+	)
+	.threads(4)
+	.health_api((HEALTH_PATH, "system_health"))
+	.rest_api(if server.cors.is_some() {
+		http::RestApi::Secure
+	} else {
+		http::RestApi::Unsecure
+	})
+	.cors(map_domains::<http::AccessControlAllowOrigin>(&server.cors))
+	.allowed_hosts(map_domains(&server.hosts))
+	.max_request_body_size(MAX_PAYLOAD)
+	.request_middleware(
+		move |request: http::hyper::Request<http::hyper::Body>| -> http::RequestMiddlewareAction {
+			if request.uri().path() == "/live" {
+				return http::Response::ok("OK\n").into();
+			}
+			let identity = match authenticator.identify(http_token(&request)) {
+				Ok(identity) => identity,
+				Err(_) => return unauthorized_http().into(),
+			};
+			if request.uri().path() == METRICS_PATH {
+				return if authenticator
+					.is_allowed(identity.as_ref().map(String::as_str), METRICS_METHOD)
+				{
+					metrics_http().into()
+				} else {
+					unauthorized_http().into()
+				};
+			}
+			request.into()
+		},
+	)
+	.start_http(addr)
 }
 
 /// Start WS server listening on given address.
-pub fn start_ws(addr: &std::net::SocketAddr, io: RpcHandler) -> io::Result<ws::Server> {
+pub fn start_ws(
+	addr: &std::net::SocketAddr,
+	server: &Server,
+	authenticator: Authenticator,
+	networks: Networks,
+	config: SharedConfig,
+	io: RpcHandler,
+) -> io::Result<ws::Server> {
 	ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| {
-		Metadata::new(context.sender())
+		let connection = WS_CONNECTION.with(|connection| connection.borrow_mut().take());
+		let (identity, client_ip, network) = match connection {
+			Some(connection) => (
+				connection.identity,
+				connection.client_ip,
+				connection.network,
+			),
+			None => (None, None, None),
+		};
+		let metadata = Metadata::new(context.sender())
+			.with_identity(identity)
+			.with_client_ip(client_ip)
+			.with_network(network);
+		if let Some(session) = metadata.session() {
+			metrics::ws_session_opened();
+			session.on_drop(metrics::ws_session_closed);
+		}
+		metadata
 	})
 	.max_payload(MAX_PAYLOAD)
+	.allowed_origins(map_domains(&server.ws_origins))
+	.allowed_hosts(map_domains(&server.hosts))
+	.request_middleware(move |request: &ws::ws::Request| -> ws::MiddlewareAction {
+		WS_CONNECTION.with(|cell| cell.borrow_mut().take());
+		match authenticator.identify(auth::query_token(request.resource())) {
+			Ok(identity) => {
+				let header = |name| {
+					request
+						.header(name)
+						.and_then(|value| std::str::from_utf8(value).ok())
+				};
+				let client_ip = forwarded_ip(
+					header("x-forwarded-for"),
+					header("x-real-ip"),
+					&trusted_proxies(&config),
+				);
+				WS_CONNECTION.with(|cell| {
+					*cell.borrow_mut() = Some(WsConnection {
+						identity,
+						client_ip,
+						network: request_network(request.resource(), |x| networks.get(x).is_some()),
+					})
+				});
+				ws::MiddlewareAction::Proceed
+			}
+			Err(_) => ws::MiddlewareAction::Respond {
+				response: ws::ws::Response::new(401, "Unauthorized", b"Unauthorized".to_vec()),
+				validate_origin: true,
+				validate_hosts: true,
+			},
+		}
+	})
 	.start(addr)
 	.map_err(|err| match err {
 		ws::Error(ws::ErrorKind::Io(io), _) => io,
@@ -49,3 +203,172 @@ pub fn start_ws(addr: &std::net::SocketAddr, io: RpcHandler) -> io::Result<ws::S
 		}
 	})
 }
+
+fn map_domains<T: for<'a> From<&'a str>>(
+	domains: &Option<Vec<String>>,
+) -> http::DomainsValidation<T> {
+	domains
+		.as_ref()
+		.map(|x| {
+			x.iter()
+				.map(AsRef::as_ref)
+				.map(Into::into)
+				.collect::<Vec<_>>()
+		})
+		.into()
+}
+
+/// Network requested by the first segment of the path, or by the `network` query parameter.
+///
+/// The first segment is only taken if it names a network, as it may also be
+/// a REST method or a prefix the panel is mounted under.
+fn request_network<F>(resource: &str, is_network: F) -> Option<String>
+where
+	F: Fn(&str) -> bool,
+{
+	let path = resource.splitn(2, '?').next().unwrap_or("");
+	path.trim_matches('/')
+		.split('/')
+		.next()
+		.filter(|x| is_network(x))
+		.or_else(|| auth::query_param(resource, "network"))
+		.map(ToString::to_string)
+}
+
+/// Trusted proxies of the current config, so a reload applies to the next requests
+fn trusted_proxies(config: &SharedConfig) -> Vec<IpAddr> {
+	config
+		.get()
+		.server
+		.trusted_proxies
+		.iter()
+		.flatten()
+		// the addresses are validated when the conf file is read
+		.filter_map(|x| x.parse().ok())
+		.collect()
+}
+
+/// Client IP forwarded by the trusted proxies.
+///
+/// The HTTP and WS servers do not expose the socket peer of a request, so the forwarded
+/// headers are only read if `trusted_proxies` is configured, the panel then being reachable
+/// through these proxies only. The client IP is the last hop of `X-Forwarded-For` which is
+/// not a trusted proxy, as a client can prepend any hop, or else `X-Real-IP`.
+fn forwarded_ip(
+	x_forwarded_for: Option<&str>,
+	x_real_ip: Option<&str>,
+	trusted_proxies: &[IpAddr],
+) -> Option<String> {
+	if trusted_proxies.is_empty() {
+		return None;
+	}
+	let client_ip = match x_forwarded_for {
+		Some(hops) => hops
+			.rsplit(',')
+			.map(|hop| hop.trim().parse::<IpAddr>())
+			.find(|hop| match hop {
+				Ok(hop) => !trusted_proxies.contains(hop),
+				Err(_) => true,
+			})?
+			.ok()?,
+		None => x_real_ip?.trim().parse::<IpAddr>().ok()?,
+	};
+	Some(client_ip.to_string())
+}
+
+fn http_token(request: &http::hyper::Request<http::hyper::Body>) -> Option<&str> {
+	request
+		.headers()
+		.get(http::hyper::header::AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(auth::bearer_token)
+}
+
+fn unauthorized_http() -> http::Response {
+	http::Response {
+		code: http::hyper::StatusCode::UNAUTHORIZED,
+		content_type: http::hyper::header::HeaderValue::from_static("text/plain; charset=utf-8"),
+		content: "Unauthorized\n".to_string(),
+	}
+}
+
+fn metrics_http() -> http::Response {
+	let (content_type, content) = metrics::encode();
+	http::Response {
+		code: http::hyper::StatusCode::OK,
+		content_type: http::hyper::header::HeaderValue::from_str(&content_type)
+			.unwrap_or_else(|_| http::hyper::header::HeaderValue::from_static("text/plain")),
+		content,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_request_network() {
+		let is_network = |x: &str| x == "mainnet" || x == "testnet";
+
+		assert_eq!(request_network("/", is_network), None);
+		assert_eq!(
+			request_network("/testnet", is_network),
+			Some("testnet".to_string())
+		);
+		assert_eq!(
+			request_network("/testnet/?token=secret", is_network),
+			Some("testnet".to_string())
+		);
+		assert_eq!(
+			request_network("/?network=testnet", is_network),
+			Some("testnet".to_string())
+		);
+
+		// rest api
+		assert_eq!(request_network("/chain_getBestNumber/0", is_network), None);
+		assert_eq!(
+			request_network("/chain_getBestNumber/0?network=testnet", is_network),
+			Some("testnet".to_string())
+		);
+
+		// mounted under a prefix
+		assert_eq!(request_network("/rpc", is_network), None);
+		assert_eq!(request_network("/ws/", is_network), None);
+		assert_eq!(
+			request_network("/rpc?network=testnet", is_network),
+			Some("testnet".to_string())
+		);
+	}
+
+	#[test]
+	fn test_forwarded_ip() {
+		let trusted = ["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()];
+		let forwarded =
+			|x_forwarded_for, x_real_ip| forwarded_ip(x_forwarded_for, x_real_ip, &trusted);
+
+		// not read without trusted proxies
+		assert_eq!(forwarded_ip(Some("1.1.1.1"), Some("1.1.1.1"), &[]), None);
+
+		assert_eq!(
+			forwarded(Some("1.1.1.1"), None),
+			Some("1.1.1.1".to_string())
+		);
+		assert_eq!(
+			forwarded(Some(" 1.1.1.1 , 10.0.0.2"), Some("2.2.2.2")),
+			Some("1.1.1.1".to_string())
+		);
+		// a client can prepend any hop, only the hops added by trusted proxies count
+		assert_eq!(
+			forwarded(Some("3.3.3.3, 1.1.1.1, 10.0.0.1"), None),
+			Some("1.1.1.1".to_string())
+		);
+		assert_eq!(forwarded(Some("1.1.1.1, unknown"), None), None);
+		assert_eq!(forwarded(Some("10.0.0.1, 10.0.0.2"), None), None);
+		assert_eq!(
+			forwarded(None, Some("2.2.2.2")),
+			Some("2.2.2.2".to_string())
+		);
+		assert_eq!(forwarded(None, Some("unknown")), None);
+		assert_eq!(forwarded(None, None), None);
+	}
+}
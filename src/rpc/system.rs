@@ -0,0 +1,165 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use futures::future;
+use futures::future::Future;
+use jsonrpc_core::BoxFuture;
+use jsonrpc_derive::rpc;
+
+use crate::config::{Networks, SharedConfig};
+use crate::rpc::client::RpcClient;
+use crate::rpc::context;
+use crate::rpc::errors;
+use crate::rpc::metrics;
+use crate::rpc::serde::Hex;
+use crate::rpc::types::{BlockNumber, Header, Health, ShardHealth};
+
+#[rpc]
+pub trait SystemApi {
+	/// Fails with `NotReady` if any shard has no reachable upstream,
+	/// so that `/health` responds 503.
+	#[rpc(name = "system_health")]
+	fn health(&self) -> BoxFuture<Health>;
+}
+
+pub struct System {
+	config: SharedConfig,
+	networks: Networks,
+}
+
+impl System {
+	/// Create new System API RPC handler.
+	pub fn new(config: SharedConfig, networks: Networks) -> Self {
+		Self { config, networks }
+	}
+}
+
+impl SystemApi for System {
+	fn health(&self) -> BoxFuture<Health> {
+		let network = context::network();
+		let shard_count = match self.networks.get(&network) {
+			Some(info) => info.shard_count,
+			None => {
+				return Box::new(future::err(
+					errors::Error::from(errors::ErrorKind::UnknownNetwork(network)).into(),
+				))
+			}
+		};
+		let rpc_client = Arc::new(RpcClient::new(self.config.clone(), network));
+
+		let shards = (0..shard_count)
+			.map(|shard_num| get_shard_health_future(rpc_client.clone(), shard_num))
+			.collect::<Vec<_>>();
+
+		let result = future::join_all(shards).and_then(|shards| {
+			let ready = !shards.is_empty() && shards.iter().all(|x| x.reachable);
+			let health = Health { ready, shards };
+			if ready {
+				Ok(health)
+			} else {
+				let health = serde_json::to_value(&health)
+					.map_err(|e| errors::Error::decode("health", e))?;
+				Err(errors::Error::from(errors::ErrorKind::NotReady(health)).into())
+			}
+		});
+
+		Box::new(result)
+	}
+}
+
+fn get_shard_health_future(rpc_client: Arc<RpcClient>, shard_num: u16) -> BoxFuture<ShardHealth> {
+	let uris = rpc_client.get_rpc_uris(shard_num).unwrap_or_default();
+	let upstreams = uris.len();
+
+	let heights = uris
+		.into_iter()
+		.map(|uri| {
+			get_upstream_heights_future(rpc_client.clone(), uri, shard_num).then(
+				|x| -> jsonrpc_core::Result<Option<(BlockNumber, BlockNumber)>> { Ok(x.ok()) },
+			)
+		})
+		.collect::<Vec<_>>();
+
+	let result = future::join_all(heights).map(move |heights| {
+		let heights = heights.into_iter().filter_map(|x| x).collect::<Vec<_>>();
+
+		let best_number = heights.iter().map(|x| x.0).max();
+		let finalized_number = heights.iter().map(|x| x.1).max();
+
+		if let Some(best_number) = best_number {
+			metrics::set_best_number(rpc_client.network(), shard_num, best_number);
+		}
+		if let Some(finalized_number) = finalized_number {
+			metrics::set_finalized_number(rpc_client.network(), shard_num, finalized_number);
+		}
+
+		let lag = match (best_number, finalized_number) {
+			(Some(best_number), Some(finalized_number)) => {
+				Some(best_number.saturating_sub(finalized_number))
+			}
+			_ => None,
+		};
+
+		ShardHealth {
+			shard_num,
+			reachable: !heights.is_empty(),
+			upstreams,
+			reachable_upstreams: heights.len(),
+			best_number,
+			finalized_number,
+			lag,
+		}
+	});
+
+	Box::new(result)
+}
+
+/// Get the best number and finalized number of an upstream
+fn get_upstream_heights_future(
+	rpc_client: Arc<RpcClient>,
+	uri: String,
+	shard_num: u16,
+) -> BoxFuture<(BlockNumber, BlockNumber)> {
+	let best: BoxFuture<Option<Header>> =
+		rpc_client.call_uri_method_async(uri.clone(), "chain_getHeader", (), shard_num);
+
+	let finalized_hash: BoxFuture<Option<Hex<Vec<u8>>>> =
+		rpc_client.call_uri_method_async(uri.clone(), "chain_getFinalizedHead", (), shard_num);
+
+	let finalized = finalized_hash.and_then(move |hash| -> BoxFuture<Option<Header>> {
+		match hash {
+			Some(hash) => rpc_client.call_uri_method_async(
+				uri,
+				"chain_getHeader",
+				(hash.to_string(),),
+				shard_num,
+			),
+			None => Box::new(future::ok(None)),
+		}
+	});
+
+	let result = best
+		.join(finalized)
+		.and_then(|(best, finalized)| match (best, finalized) {
+			(Some(best), Some(finalized)) => Ok((best.number, finalized.number)),
+			_ => Err(errors::Error::decode("header", "no header").into()),
+		});
+
+	Box::new(result)
+}
@@ -0,0 +1,140 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use futures::{Future, Stream};
+use log::{debug, info, warn};
+use tokio::io::AsyncRead;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+use tokio_rustls::rustls::internal::pemfile;
+use tokio_rustls::rustls::{NoClientAuth, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::Tls;
+use crate::rpc::errors;
+use crate::watch;
+
+/// TLS certificate and key, reloaded when the files change
+pub struct Certificates {
+	cert: PathBuf,
+	key: PathBuf,
+	acceptor: RwLock<TlsAcceptor>,
+}
+
+impl Certificates {
+	pub fn load(tls: &Tls) -> errors::Result<Arc<Self>> {
+		let cert = PathBuf::from(&tls.cert);
+		let key = PathBuf::from(&tls.key);
+		let acceptor = RwLock::new(load_acceptor(&cert, &key)?);
+
+		let certificates = Arc::new(Certificates {
+			cert,
+			key,
+			acceptor,
+		});
+
+		let watched = certificates.clone();
+		watch::watch_files(
+			"tls",
+			vec![certificates.cert.clone(), certificates.key.clone()],
+			move || watched.reload(),
+		);
+
+		Ok(certificates)
+	}
+
+	fn reload(&self) {
+		match load_acceptor(&self.cert, &self.key) {
+			Ok(acceptor) => {
+				*self.acceptor.write().expect("qed") = acceptor;
+				info!("Reloaded TLS certificate: {}", self.cert.to_string_lossy());
+			}
+			Err(e) => warn!("Failed to reload TLS certificate, keep the old one: {}", e),
+		}
+	}
+
+	fn acceptor(&self) -> TlsAcceptor {
+		self.acceptor.read().expect("qed").clone()
+	}
+}
+
+/// Terminate TLS on `addr` and forward the plain stream to `target`.
+///
+/// The returned runtime serves the connections until dropped.
+pub fn start_proxy(
+	addr: &SocketAddr,
+	target: SocketAddr,
+	certificates: Arc<Certificates>,
+) -> errors::Result<Runtime> {
+	let listener = TcpListener::bind(addr)?;
+
+	let server = listener
+		.incoming()
+		.map_err(|e| warn!("TLS accept error: {:?}", e))
+		.for_each(move |stream| {
+			let proxy = certificates
+				.acceptor()
+				.accept(stream)
+				.and_then(move |tls| TcpStream::connect(&target).map(|plain| (tls, plain)))
+				.and_then(|(tls, plain)| {
+					let (tls_reader, tls_writer) = tls.split();
+					let (plain_reader, plain_writer) = plain.split();
+					let request = tokio::io::copy(tls_reader, plain_writer)
+						.and_then(|(_, _, writer)| tokio::io::shutdown(writer));
+					let response = tokio::io::copy(plain_reader, tls_writer)
+						.and_then(|(_, _, writer)| tokio::io::shutdown(writer));
+					request.join(response).map(|_| ())
+				})
+				.map_err(|e| debug!("TLS connection error: {:?}", e));
+			tokio::spawn(proxy);
+			Ok(())
+		});
+
+	let mut runtime = Runtime::new()?;
+	runtime.spawn(server);
+
+	Ok(runtime)
+}
+
+fn load_acceptor(cert: &Path, key: &Path) -> errors::Result<TlsAcceptor> {
+	let certs = pemfile::certs(&mut BufReader::new(File::open(cert)?))
+		.map_err(|_| format!("Invalid TLS certificate: {}", cert.to_string_lossy()))?;
+
+	let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(key)?))
+		.map_err(|_| format!("Invalid TLS key: {}", key.to_string_lossy()))?;
+	if keys.is_empty() {
+		keys = pemfile::rsa_private_keys(&mut BufReader::new(File::open(key)?))
+			.map_err(|_| format!("Invalid TLS key: {}", key.to_string_lossy()))?;
+	}
+	let key = keys
+		.into_iter()
+		.next()
+		.ok_or_else(|| format!("No TLS key found in: {}", key.to_string_lossy()))?;
+
+	let mut config = ServerConfig::new(NoClientAuth::new());
+	config
+		.set_single_cert(certs, key)
+		.map_err(|e| format!("Invalid TLS certificate: {}", e))?;
+
+	Ok(TlsAcceptor::from(Arc::new(config)))
+}
@@ -17,9 +17,9 @@
 
 use parity_codec::{Compact, Decode};
 use parity_codec::Encode;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde::export::TryFrom;
-use serde_json::Value;
+use serde_json::{json, Value};
 use substrate_primitives::blake2_256;
 use substrate_primitives::storage::StorageKey;
 use yee_primitives::AddressCodec;
@@ -27,7 +27,9 @@ use yee_sharding_primitives::utils::shard_num_for_bytes;
 use yee_signer::tx::call::Call;
 use yee_signer::tx::types::{Era, Transaction};
 
-use crate::config::{HRP, SHARD_COUNT};
+use crate::config::{BalanceFormat, Format, NetworkInfo};
+use crate::rpc::builder;
+use crate::rpc::decoder::Decoder;
 use crate::rpc::errors;
 use crate::rpc::serde::{Hex, SerdeHex};
 
@@ -38,9 +40,6 @@ pub type BlockNumber = u64;
 
 pub type Nonce = u64;
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Balance(pub u128);
-
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Header {
@@ -70,6 +69,14 @@ pub struct Block {
 	pub extrinsics: Vec<String>,
 }
 
+/// Runtime version of a block, other fields of `state_getRuntimeVersion` are ignored
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeVersion {
+	pub spec_name: String,
+	pub spec_version: u32,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ResultHeader {
 	pub block_hash: Option<Hex<Vec<u8>>>,
@@ -101,42 +108,50 @@ impl From<Header> for ResultHeader {
 #[derive(Serialize, Debug)]
 pub struct ResultBlock {
 	pub header: ResultHeader,
+	/// Runtime spec version the block was produced with
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub spec_version: Option<u32>,
 	pub extrinsics: Vec<ResultTransaction>,
 }
 
-impl TryFrom<Block> for ResultBlock {
-	type Error = errors::Error;
-
-	fn try_from(t: Block) -> Result<Self, Self::Error> {
+impl ResultBlock {
+	/// Convert the block, an extrinsic which can not be decoded is kept raw with a `decode_error`
+	pub fn from_block(t: Block, network: &NetworkInfo, decoder: &dyn Decoder) -> Self {
 		// extrinsics
 		let extrinsics = t.extrinsics;
 		let extrinsics = extrinsics
 			.into_iter()
 			.enumerate()
-			.map(|(index, x)| -> errors::Result<ResultTransaction> {
-				let mut x = hex_decode(&x)?;
-				let mut length_prefix: Vec<u8> = Compact(x.len() as u32).encode();
-				length_prefix.append(&mut x);
-				let raw = length_prefix;
-				let x: Transaction =
-					Decode::decode(&mut &raw[..]).ok_or(errors::ErrorKind::ParseError)?;
-				let mut x: ResultTransaction = x.into();
-
-				let hash = blake2_256(&raw);
-				x.hash = Some(Hex(hash.to_vec()));
-				x.index = Some(index as u32);
-				x.raw = Some(Hex(raw));
-
-				Ok(x)
+			.map(|(index, x)| {
+				let raw = match hex_decode(&x) {
+					Ok(mut x) => {
+						let mut length_prefix: Vec<u8> = Compact(x.len() as u32).encode();
+						length_prefix.append(&mut x);
+						length_prefix
+					}
+					Err(e) => return ResultTransaction::undecodable(None, index as u32, e),
+				};
+				match decoder.decode_transaction(&raw) {
+					Some(x) => {
+						let mut x = ResultTransaction::from_transaction(x, network);
+						x.hash = Some(Hex(blake2_256(&raw).to_vec()));
+						x.index = Some(index as u32);
+						x.raw = Some(Hex(raw));
+						x
+					}
+					None => {
+						let e = errors::Error::decode("extrinsic", "unknown transaction format");
+						ResultTransaction::undecodable(Some(raw), index as u32, e)
+					}
+				}
 			})
-			.collect::<errors::Result<Vec<_>>>()?;
-
-		// success
+			.collect();
 
-		Ok(ResultBlock {
+		ResultBlock {
 			header: t.header.into(),
+			spec_version: None,
 			extrinsics: extrinsics,
-		})
+		}
 	}
 }
 
@@ -144,9 +159,18 @@ impl TryFrom<ResultBlock> for Value {
 	type Error = errors::Error;
 
 	fn try_from(x: ResultBlock) -> Result<Self, Self::Error> {
-		let x = serde_json::to_vec(&x).map_err(|_| errors::ErrorKind::ParseError)?;
-		let x = serde_json::from_slice(&x).map_err(|_| errors::ErrorKind::ParseError)?;
-		Ok(x)
+		let transfer_values = x
+			.extrinsics
+			.iter()
+			.map(|tx| tx.transfer_value)
+			.collect::<Vec<_>>();
+		let mut value = to_json_value(&x)?;
+		if let Some(extrinsics) = value.get_mut("extrinsics").and_then(Value::as_array_mut) {
+			for (extrinsic, transfer_value) in extrinsics.iter_mut().zip(transfer_values) {
+				set_transfer_value(extrinsic, transfer_value);
+			}
+		}
+		Ok(value)
 	}
 }
 
@@ -154,22 +178,52 @@ impl TryFrom<ResultTransaction> for Value {
 	type Error = errors::Error;
 
 	fn try_from(x: ResultTransaction) -> Result<Self, Self::Error> {
-		let x = serde_json::to_vec(&x).map_err(|_| errors::ErrorKind::ParseError)?;
-		let x = serde_json::from_slice(&x).map_err(|_| errors::ErrorKind::ParseError)?;
-		Ok(x)
+		let mut value = to_json_value(&x)?;
+		set_transfer_value(&mut value, x.transfer_value);
+		Ok(value)
 	}
 }
 
-impl TryFrom<Balance> for Value {
-	type Error = errors::Error;
+/// Convert through the json text, as the calls do not convert to `Value` directly
+fn to_json_value<T: Serialize>(x: &T) -> errors::Result<Value> {
+	let x = serde_json::to_vec(x).map_err(|e| errors::Error::decode("result", e))?;
+	let x = serde_json::from_slice(&x).map_err(|e| errors::Error::decode("result", e))?;
+	Ok(x)
+}
 
-	fn try_from(x: Balance) -> Result<Self, Self::Error> {
-		let x = serde_json::to_vec(&x).map_err(|_| errors::ErrorKind::ParseError)?;
-		let x = serde_json::from_slice(&x).map_err(|_| errors::ErrorKind::ParseError)?;
-		Ok(x)
+/// Replace the transfer value of the converted call, a lossy number above u64::MAX,
+/// with the exact decimal string
+fn set_transfer_value(extrinsic: &mut Value, transfer_value: Option<u128>) {
+	let params = extrinsic
+		.get_mut("call")
+		.and_then(|call| call.get_mut("params"))
+		.and_then(Value::as_object_mut);
+	if let (Some(params), Some(transfer_value)) = (params, transfer_value) {
+		params.insert(
+			"value".to_string(),
+			Value::String(transfer_value.to_string()),
+		);
 	}
 }
 
+#[derive(Serialize, Debug)]
+pub struct Health {
+	pub ready: bool,
+	pub shards: Vec<ShardHealth>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ShardHealth {
+	pub shard_num: u16,
+	pub reachable: bool,
+	pub upstreams: usize,
+	pub reachable_upstreams: usize,
+	pub best_number: Option<BlockNumber>,
+	pub finalized_number: Option<BlockNumber>,
+	/// Best number minus finalized number
+	pub lag: Option<BlockNumber>,
+}
+
 #[derive(Serialize, Debug)]
 pub struct ResultSignature {
 	#[serde(with = "SerdeHex")]
@@ -188,11 +242,22 @@ pub struct ResultTransaction {
 	pub raw: Option<Hex<Vec<u8>>>,
 	pub hash: Option<Hex<Vec<u8>>>,
 	pub signature: Option<ResultSignature>,
-	pub call: Call,
+	/// None if the extrinsic could not be decoded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub call: Option<Call>,
 	pub index: Option<u32>,
 	pub success: Option<bool>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub block_number: Option<BlockNumber>,
+	/// Why a part of the extrinsic, such as the signature, could not be resolved
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// Why the extrinsic could not be decoded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub decode_error: Option<String>,
+	/// Value of a balances transfer call
+	#[serde(skip)]
+	pub transfer_value: Option<u128>,
 }
 
 #[derive(Serialize, Debug)]
@@ -210,31 +275,278 @@ impl From<Era> for ResultEra {
 	}
 }
 
-impl From<Transaction> for ResultTransaction {
-	fn from(t: Transaction) -> Self {
-		let hrp = HRP.read().expect("qed").clone();
-		let shard_count = SHARD_COUNT.read().expect("qed").clone();
-		let signature = t.signature.map(|(sender, sig, nonce, era)| {
-			let public = sender.0[1..].to_vec();
-			let shard_num = shard_num_for_bytes(&public, shard_count).expect("qed");
-			let address = public.to_address(hrp).expect("qed");
-			ResultSignature {
-				sender: sender.0.to_vec(),
-				sender_address: address.0,
-				sender_shard_num: shard_num,
-				signature: sig.to_vec(),
-				nonce: nonce.0,
-				era: era.into(),
-			}
-		});
+impl ResultTransaction {
+	/// Convert the transaction, a signature which can not be resolved is left out with an error
+	pub fn from_transaction(t: Transaction, network: &NetworkInfo) -> Self {
+		let signature = t.signature.map(
+			|(sender, sig, nonce, era)| -> errors::Result<ResultSignature> {
+				let (address, shard_num) = public_to_address(&sender.0[1..], network)?;
+				Ok(ResultSignature {
+					sender: sender.0.to_vec(),
+					sender_address: address,
+					sender_shard_num: shard_num,
+					signature: sig.to_vec(),
+					nonce: nonce.0,
+					era: era.into(),
+				})
+			},
+		);
+		let (signature, error) = match signature {
+			Some(Ok(signature)) => (Some(signature), None),
+			Some(Err(e)) => (None, Some(e.to_string())),
+			None => (None, None),
+		};
 		Self {
 			raw: None,
 			hash: None,
 			signature,
-			call: t.call,
+			transfer_value: builder::transfer_value(&t.call),
+			call: Some(t.call),
 			index: None,
 			success: None,
 			block_number: None,
+			error,
+			decode_error: None,
+		}
+	}
+
+	/// Extrinsic of a block which could not be decoded, possibly of a call added by a runtime upgrade
+	pub fn undecodable(raw: Option<Vec<u8>>, index: u32, e: errors::Error) -> Self {
+		Self {
+			hash: raw.as_ref().map(|raw| Hex(blake2_256(raw).to_vec())),
+			raw: raw.map(Hex),
+			signature: None,
+			call: None,
+			index: Some(index),
+			success: None,
+			block_number: None,
+			error: None,
+			decode_error: Some(e.to_string()),
+			transfer_value: None,
+		}
+	}
+}
+
+/// Address and shard number of a public key in the network
+pub fn public_to_address(public: &[u8], network: &NetworkInfo) -> errors::Result<(String, u16)> {
+	if public.len() != 32 {
+		return Err(errors::Error::decode(
+			"public",
+			format!("expected 32 bytes, got {}", public.len()),
+		));
+	}
+	let shard_num = shard_num_for_bytes(public, network.shard_count).ok_or_else(|| {
+		errors::Error::from(errors::ErrorKind::InvalidShard(None, network.shard_count))
+	})?;
+	let address = public
+		.to_vec()
+		.to_address(network.hrp.clone())
+		.map_err(|e| errors::Error::decode("address", format!("{:?}", e)))?;
+	Ok((address.0, shard_num))
+}
+
+/// Decimal digits of the share of the total issuance of an account
+const ISSUANCE_SHARE_DECIMALS: u8 = 9;
+
+/// Lock on the free balance, of `srml-balances`
+#[derive(Decode, Debug, Clone)]
+pub struct BalanceLock {
+	pub id: [u8; 8],
+	pub amount: u128,
+	pub until: BlockNumber,
+	/// `WithdrawReasons` bit flags
+	pub reasons: i8,
+}
+
+/// Vesting of the free balance, of `srml-balances`
+#[derive(Decode, Debug, Clone, Default)]
+pub struct VestingSchedule {
+	/// Locked at block 0
+	pub offset: u128,
+	/// Unlocked per block
+	pub per_block: u128,
+}
+
+impl VestingSchedule {
+	pub fn locked_at(&self, block_number: BlockNumber) -> u128 {
+		self.offset
+			.saturating_sub(self.per_block.saturating_mul(u128::from(block_number)))
+	}
+}
+
+/// State of an account at a block
+#[derive(Debug, Clone)]
+pub struct Account {
+	pub block_number: BlockNumber,
+	pub free: u128,
+	pub reserved: u128,
+	pub total_issuance: u128,
+	pub nonce: Nonce,
+	pub locks: Vec<BalanceLock>,
+	pub vesting: Option<VestingSchedule>,
+}
+
+impl Account {
+	/// Free balance not restricted by the locks or the vesting at the block.
+	///
+	/// All locks are taken into account whatever their withdraw reasons.
+	pub fn spendable(&self) -> u128 {
+		let locked = self
+			.locks
+			.iter()
+			.filter(|lock| lock.until > self.block_number)
+			.map(|lock| lock.amount)
+			.chain(self.vesting.iter().map(|x| x.locked_at(self.block_number)))
+			.max()
+			.unwrap_or(0);
+		self.free.saturating_sub(locked)
+	}
+
+	/// Share of the total issuance held by the account, `free` + `reserved`, None if nothing is issued
+	pub fn issuance_share(&self) -> Option<String> {
+		if self.total_issuance == 0 {
+			return None;
+		}
+		let total = self.free.saturating_add(self.reserved);
+		let unit = 10u128.pow(u32::from(ISSUANCE_SHARE_DECIMALS));
+		let share = match total.checked_mul(unit) {
+			Some(x) => x / self.total_issuance,
+			None => total / (self.total_issuance / unit).max(1),
+		};
+		Some(format_amount(share, ISSUANCE_SHARE_DECIMALS))
+	}
+
+	pub fn to_value(&self, format: Format) -> Value {
+		let locks = self
+			.locks
+			.iter()
+			.map(|lock| {
+				json!({
+					"id": Hex(lock.id.to_vec()),
+					"amount": amount_value(lock.amount, format),
+					"until": lock.until,
+					"reasons": lock.reasons,
+				})
+			})
+			.collect::<Vec<_>>();
+		let vesting = self.vesting.as_ref().map(|vesting| {
+			json!({
+				"offset": amount_value(vesting.offset, format),
+				"per_block": amount_value(vesting.per_block, format),
+				"locked": amount_value(vesting.locked_at(self.block_number), format),
+			})
+		});
+		json!({
+			"block_number": self.block_number,
+			"free": amount_value(self.free, format),
+			"reserved": amount_value(self.reserved, format),
+			"total": amount_value(self.free.saturating_add(self.reserved), format),
+			"spendable": amount_value(self.spendable(), format),
+			"total_issuance": amount_value(self.total_issuance, format),
+			"issuance_share": self.issuance_share(),
+			"nonce": self.nonce,
+			"locks": locks,
+			"vesting": vesting,
+		})
+	}
+}
+
+/// Amount in the configured format, with the formatted amount if decimals are configured
+pub fn amount_value(amount: u128, format: Format) -> Value {
+	match format.decimals {
+		Some(decimals) => json!({
+			"amount": balance_value(amount, format.balance),
+			"formatted": format_amount(amount, decimals),
+		}),
+		None => balance_value(amount, format.balance),
+	}
+}
+
+/// Balance in the configured format
+pub fn balance_value(amount: u128, balance_format: BalanceFormat) -> Value {
+	match balance_format {
+		BalanceFormat::String => Value::String(amount.to_string()),
+		BalanceFormat::Hex => Value::String(Hex(amount).to_string()),
+		BalanceFormat::Number => serde_json::from_str(&amount.to_string()).unwrap_or(Value::Null),
+	}
+}
+
+/// Amount in base units formatted with the decimals of the token, e.g. `9.40254322`
+pub fn format_amount(amount: u128, decimals: u8) -> String {
+	let unit = 10u128.pow(u32::from(decimals));
+	if decimals == 0 {
+		return amount.to_string();
+	}
+	format!(
+		"{}.{:0width$}",
+		amount / unit,
+		amount % unit,
+		width = decimals as usize
+	)
+}
+
+/// Amount in base units of a formatted amount, None if malformed or too precise
+pub fn parse_amount(amount: &str, decimals: u8) -> Option<u128> {
+	let mut parts = amount.splitn(2, '.');
+	let integer = parts.next().unwrap_or("");
+	let fraction = parts.next().unwrap_or("");
+	let decimals = decimals as usize;
+	let is_digits = |x: &str| x.chars().all(|c| c.is_ascii_digit());
+	if (integer.is_empty() && fraction.is_empty())
+		|| !is_digits(integer)
+		|| !is_digits(fraction)
+		|| fraction.len() > decimals
+	{
+		return None;
+	}
+	let digits = format!("{}{:0<width$}", integer, fraction, width = decimals);
+	let digits = digits.trim_start_matches('0');
+	if digits.is_empty() {
+		return Some(0);
+	}
+	digits.parse().ok()
+}
+
+/// Amount taken by input methods
+///
+/// A number or an integer string is in base units,
+/// a string with a decimal point is formatted with the decimals of the token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Amount {
+	BaseUnits(u128),
+	Text(String),
+}
+
+impl Amount {
+	pub fn base_units(&self, decimals: Option<u8>) -> errors::Result<u128> {
+		let invalid = |amount: &str| {
+			errors::Error::from(errors::ErrorKind::InvalidAmount(amount.to_string()))
+		};
+		match self {
+			Amount::BaseUnits(amount) => Ok(*amount),
+			Amount::Text(amount) if amount.contains('.') => match decimals {
+				Some(decimals) => parse_amount(amount, decimals).ok_or_else(|| invalid(amount)),
+				None => Err(invalid(amount)),
+			},
+			Amount::Text(amount) => amount.parse().map_err(|_| invalid(amount)),
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for Amount {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		use serde::de::Error;
+
+		match Value::deserialize(deserializer)? {
+			Value::Number(amount) => amount
+				.as_u64()
+				.map(|x| Amount::BaseUnits(u128::from(x)))
+				.ok_or_else(|| D::Error::custom("expected a non-negative integer amount")),
+			Value::String(amount) => Ok(Amount::Text(amount)),
+			_ => Err(D::Error::custom("expected a number or a string amount")),
 		}
 	}
 }
@@ -272,6 +584,175 @@ fn twox(data: &[u8], seed: u64) -> Vec<u8> {
 
 fn hex_decode(str: &str) -> errors::Result<Vec<u8>> {
 	let str = str.trim_start_matches("0x");
-	let result = hex::decode(str).map_err(|_| errors::ErrorKind::ParseError)?;
+	let result = hex::decode(str).map_err(|e| errors::Error::decode("hex", e))?;
 	Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+	use yee_primitives::Hrp;
+
+	use crate::rpc::decoder;
+
+	use super::*;
+
+	#[test]
+	fn test_transfer_value_above_u64() {
+		// a transfer of 2^64 + 1
+		let raw = hex::decode(concat!(
+			"490281ff1033e0576822a6a836f612a193036042050e286da4561f5cc5d8ee560c64dc54",
+			"40b2595b4c269c29377b658abe62303d59f975d0914f205d5fe8c7f24974a56007b979bf73e76211c3c3826293def93b882ef03a0a48e479693c2cbcb0425c07",
+			"04b503",
+			"0400ff94d988b42d96dcbd6605ff47f19c6ab35f626eb1bc8bbd28f59a74997a253a3d",
+			"17010000000000000001",
+		))
+		.unwrap();
+		let network = NetworkInfo {
+			hrp: Hrp::MAINNET,
+			shard_count: 4,
+		};
+		let tx = decoder::get(None).decode_transaction(&raw).unwrap();
+		let tx = ResultTransaction::from_transaction(tx, &network);
+		assert_eq!(tx.transfer_value, Some((1u128 << 64) + 1));
+
+		let value = Value::try_from(tx).unwrap();
+		assert_eq!(
+			value["call"]["params"]["value"],
+			json!("18446744073709551617")
+		);
+	}
+
+	#[test]
+	fn test_undecodable_extrinsic() {
+		let block = Block {
+			header: Header {
+				extrinsics_root: vec![],
+				parent_hash: vec![],
+				state_root: vec![],
+				number: 1,
+			},
+			extrinsics: vec!["0xzz".to_string(), "0xff".to_string()],
+		};
+		let network = NetworkInfo {
+			hrp: Hrp::TESTNET,
+			shard_count: 4,
+		};
+		let block = ResultBlock::from_block(block, &network, &*decoder::get(None));
+		assert_eq!(block.extrinsics.len(), 2);
+
+		let bad_hex = &block.extrinsics[0];
+		assert!(bad_hex.raw.is_none());
+		assert!(bad_hex.decode_error.is_some());
+		assert_eq!(bad_hex.index, Some(0));
+
+		let unknown = &block.extrinsics[1];
+		assert!(unknown.call.is_none());
+		assert!(unknown.decode_error.is_some());
+		assert_eq!(
+			unknown.raw.as_ref().map(|x| x.0.clone()),
+			Some(vec![0x04, 0xff])
+		);
+		assert!(unknown.hash.is_some());
+		assert_eq!(unknown.index, Some(1));
+	}
+
+	#[test]
+	fn test_balance_value() {
+		let amount = 10_000_000_000_000_000_000u128;
+		assert_eq!(
+			balance_value(amount, BalanceFormat::String),
+			Value::String("10000000000000000000".to_string())
+		);
+		assert_eq!(
+			balance_value(amount, BalanceFormat::Hex),
+			Value::String("0x8ac7230489e80000".to_string())
+		);
+		assert_eq!(
+			balance_value(amount, BalanceFormat::Number),
+			serde_json::json!(10_000_000_000_000_000_000u64)
+		);
+	}
+
+	#[test]
+	fn test_amount() {
+		assert_eq!(format_amount(940254322, 8), "9.40254322");
+		assert_eq!(format_amount(1, 8), "0.00000001");
+		assert_eq!(format_amount(100, 0), "100");
+
+		assert_eq!(parse_amount("9.40254322", 8), Some(940254322));
+		assert_eq!(parse_amount("1.5", 8), Some(150000000));
+		assert_eq!(parse_amount(".5", 8), Some(50000000));
+		assert_eq!(parse_amount("0.0", 8), Some(0));
+		assert_eq!(parse_amount("0.000000001", 8), None);
+		assert_eq!(parse_amount("1.-5", 8), None);
+		assert_eq!(parse_amount(".", 8), None);
+
+		let amount: Amount = serde_json::from_str("150000000").unwrap();
+		assert_eq!(amount.base_units(Some(8)).unwrap(), 150000000);
+		let amount: Amount = serde_json::from_str(r#""150000000""#).unwrap();
+		assert_eq!(amount.base_units(None).unwrap(), 150000000);
+		let amount: Amount = serde_json::from_str(r#""1.5""#).unwrap();
+		assert_eq!(amount.base_units(Some(8)).unwrap(), 150000000);
+		assert!(amount.base_units(None).is_err());
+		assert!(serde_json::from_str::<Amount>("-1").is_err());
+	}
+
+	#[test]
+	fn test_account_spendable() {
+		let lock = |amount, until| BalanceLock {
+			id: *b"staking ",
+			amount,
+			until,
+			reasons: 0,
+		};
+		let mut account = Account {
+			block_number: 100,
+			free: 1000,
+			reserved: 50,
+			total_issuance: 10000,
+			nonce: 1,
+			locks: vec![lock(300, 200), lock(600, 100)],
+			vesting: None,
+		};
+		assert_eq!(account.spendable(), 700);
+
+		account.vesting = Some(VestingSchedule {
+			offset: 900,
+			per_block: 1,
+		});
+		assert_eq!(account.spendable(), 200);
+
+		account.free = 100;
+		assert_eq!(account.spendable(), 0);
+	}
+
+	#[test]
+	fn test_account_issuance_share() {
+		let mut account = Account {
+			block_number: 100,
+			free: 1000,
+			reserved: 50,
+			total_issuance: 10000,
+			nonce: 1,
+			locks: vec![],
+			vesting: None,
+		};
+		assert_eq!(account.issuance_share(), Some("0.105000000".to_string()));
+
+		account.free = 940254322;
+		account.reserved = 0;
+		account.total_issuance = 2012800000000000;
+		assert_eq!(account.issuance_share(), Some("0.000000467".to_string()));
+
+		account.free = u128::max_value();
+		account.total_issuance = u128::max_value();
+		assert_eq!(account.issuance_share(), Some("1.000000000".to_string()));
+
+		account.total_issuance = 0;
+		assert_eq!(account.issuance_share(), None);
+		assert_eq!(
+			account.to_value(Format::default())["issuance_share"],
+			Value::Null
+		);
+	}
+}
@@ -0,0 +1,55 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::warn;
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Poll the modification time of the files and call `on_change` when any of them changes.
+pub fn watch_files<F>(name: &str, paths: Vec<PathBuf>, on_change: F)
+where
+	F: Fn() + Send + 'static,
+{
+	let mut last = modified(&paths);
+
+	let result = thread::Builder::new()
+		.name(format!("watch-{}", name))
+		.spawn(move || loop {
+			thread::sleep(WATCH_INTERVAL);
+			let current = modified(&paths);
+			if current != last {
+				last = current;
+				on_change();
+			}
+		});
+
+	if let Err(e) = result {
+		warn!("Failed to watch {}: {:?}", name, e);
+	}
+}
+
+fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+	paths
+		.iter()
+		.map(|path| fs::metadata(path).and_then(|x| x.modified()).ok())
+		.collect()
+}